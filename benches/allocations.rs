@@ -0,0 +1,142 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! Counts the heap allocations made by filter building and repeated opens of
+//! the same builder, which reuse the filter converted on the first open, and with
+//! the `pool` feature those saved by recycling returned paths.
+//!
+//! `cargo bench --bench allocations`, adding `--features pool,integration-sim` for
+//! the pool; there are no timings, only counts, printed per operation.
+
+extern crate nfd;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nfd::Filter;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ROUNDS: usize = 1000;
+
+const GROUPS: &[(&str, &[&str])] = &[("Images", &["png", "jpg", "gif"]), ("Documents", &["pdf", "txt"])];
+
+/// Average number of allocations made by one call of `f`
+fn allocations_per_call<F: FnMut()>(mut f: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ROUNDS {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ROUNDS as f64
+}
+
+/// Number of allocations made by a single call of `f`
+fn allocations_per_call_once<F: FnOnce()>(f: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64
+}
+
+fn report(name: &str, allocations: f64) {
+    println!("{:<40} {:>6.1} allocations", name, allocations);
+}
+
+fn filter_building() {
+    let mut builder = nfd::dialog();
+    report("filter_parts", allocations_per_call(|| {
+        builder.filter_parts(GROUPS);
+    }));
+
+    let mut builder = nfd::dialog();
+    report("Filter::group + filter", allocations_per_call(|| {
+        let filter = GROUPS.iter().fold(Filter::new(), |filter, &(name, extensions)| filter.group(name, extensions));
+        builder.filter(&filter.to_string());
+    }));
+}
+
+fn repeated_opens() {
+    // `dry_run` converts and checks everything a real open would, without a dialog
+    let mut builder = nfd::dialog();
+    builder.filter_parts(GROUPS).dry_run(true);
+    report("open, first call", allocations_per_call_once(|| {
+        builder.open().unwrap();
+    }));
+    report("open, later calls", allocations_per_call(|| {
+        builder.open().unwrap();
+    }));
+
+    report("open, new builder each call", allocations_per_call(|| {
+        nfd::dialog().filter_parts(GROUPS).dry_run(true).open().unwrap();
+    }));
+}
+
+/// Dialogs on the same thread with and without handing the paths back, on the
+/// scripted dialogs of `nfd::sim` since the native ones need a user
+#[cfg(all(feature = "pool", feature = "integration-sim"))]
+fn pooled_paths() {
+    use nfd::sim::{self, Script};
+
+    const SELECTION: &[&str] = &["/srv/upload/a.bin", "/srv/upload/b.bin", "/srv/upload/c.bin"];
+
+    let open = |recycle: bool| {
+        sim::enqueue(Script::new().select_many(SELECTION));
+        let response = nfd::dialog_multiple().open().unwrap();
+        if recycle {
+            response.recycle();
+        }
+        sim::take_events();
+    };
+    report("pooled dialog, paths dropped", allocations_per_call(|| open(false)));
+    report("pooled dialog, paths recycled", allocations_per_call(|| open(true)));
+}
+
+#[cfg(not(all(feature = "pool", feature = "integration-sim")))]
+fn pooled_paths() {
+    println!("(run with --features pool,integration-sim for the path pool)");
+}
+
+fn main() {
+    filter_building();
+    repeated_opens();
+    pooled_paths();
+}
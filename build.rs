@@ -34,6 +34,12 @@ fn main() {
     let mut cfg = cc::Build::new();
     let env = env::var("TARGET").unwrap();
 
+    // No backend for anything else (e.g. wasm); src/ffi.rs provides stubs instead
+    let family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+    if !family.split(',').any(|f| f == "unix" || f == "windows") {
+        return;
+    }
+
     cfg.include(nfd!("include"));
     cfg.file(nfd!("nfd_common.c"));
 
@@ -0,0 +1,26 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate nfd;
+
+use nfd::Filter;
+
+fuzz_target!(|data: &[u8]| {
+    let filter_list = match std::str::from_utf8(data) {
+        Ok(filter_list) => filter_list,
+        Err(_) => return,
+    };
+
+    // Must never panic, whatever the input
+    if nfd::validate_filter(filter_list).is_err() || filter_list.is_empty() {
+        return;
+    }
+
+    // A list that validates rebuilds into an equal, valid `Filter`
+    let mut filter = Filter::new();
+    for group in filter_list.split(';') {
+        let extensions: Vec<&str> = group.split(',').collect();
+        filter = filter.try_group(group, &extensions).expect("validated group rejected by try_group");
+    }
+    assert_eq!(filter.to_string(), filter_list);
+    assert!(filter.build().is_ok());
+});
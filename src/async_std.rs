@@ -0,0 +1,47 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! Dialogs for async-std programs, with the `async-std` feature.
+//!
+//! Dialogs run on async-std's blocking thread pool so they don't hold up the
+//! executor. That is a thread other than the main one, so as with
+//! `DialogBuilder::open_cancellable` they fail with `NFDError::Unsupported` on
+//! macOS, and GTK only tolerates them if no other thread uses GTK.
+
+use std::future::Future;
+use std::path::PathBuf;
+
+use async_std_crate::task;
+
+use background;
+use {open_file_dialog, Response, Result};
+
+/// Pick a single file, resolving to `None` if the user cancelled, or to
+/// `NFDError::Unsupported` on macOS without showing a dialog
+pub fn pick_file(filter_list: Option<&str>, default_path: Option<&str>) -> impl Future<Output = Result<Option<PathBuf>>> {
+    let filter_list = filter_list.map(str::to_owned);
+    let default_path = default_path.map(str::to_owned);
+    task::spawn_blocking(move || {
+        background::check_supported()?;
+        open_file_dialog(filter_list.as_deref(), default_path.as_deref()).map(Response::into_path)
+    })
+}
@@ -0,0 +1,140 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use sandbox::is_sandboxed;
+use {NFDError, Result};
+
+/// Set once the first dialog has been shown, after which the backend is fixed
+static DIALOG_SHOWN: AtomicBool = AtomicBool::new(false);
+
+static PREFERRED: Mutex<Option<Backend>> = Mutex::new(None);
+
+/// A native dialog implementation
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Backend {
+    /// `NSOpenPanel` and `NSSavePanel` on macOS
+    Cocoa,
+    /// The common item dialogs (`IFileOpenDialog`, `IFileSaveDialog`) on Windows
+    Windows,
+    /// `GtkFileChooserDialog` on Linux and other unix targets
+    Gtk,
+    /// The scripted dialogs of `nfd::sim`. Only used with the `integration-sim`
+    /// feature, but always present so that enabling it doesn't break exhaustive
+    /// matches elsewhere in the build
+    Simulated,
+    /// No backend; every dialog fails with `NFDError::Unsupported`
+    Unsupported,
+}
+
+/// The backend dialogs are shown with: the one passed to `set_preferred_backend`,
+/// or else the one picked for this build
+pub fn backend() -> Backend {
+    let preferred = *PREFERRED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    preferred.unwrap_or_else(default_backend)
+}
+
+#[cfg(feature = "integration-sim")]
+fn default_backend() -> Backend {
+    Backend::Simulated
+}
+
+#[cfg(not(feature = "integration-sim"))]
+fn default_backend() -> Backend {
+    if cfg!(target_vendor = "apple") {
+        Backend::Cocoa
+    } else if cfg!(windows) {
+        Backend::Windows
+    } else if cfg!(unix) {
+        Backend::Gtk
+    } else {
+        Backend::Unsupported
+    }
+}
+
+/// The backends this build can show dialogs with here, best first; empty if
+/// every dialog would fail with `NFDError::Unsupported`.
+///
+/// Only the backend linked in at compile time can ever be listed: the bundled
+/// nativefiledialog has exactly one per platform, and no xdg-desktop-portal
+/// backend. At runtime GTK is left out inside a Flatpak or Snap sandbox (see
+/// `is_sandboxed`), where its dialogs can't reach the host's files.
+pub fn available_backends() -> Vec<Backend> {
+    match default_backend() {
+        Backend::Unsupported => Vec::new(),
+        Backend::Gtk if is_sandboxed() => Vec::new(),
+        backend => vec![backend],
+    }
+}
+
+/// Use `backend` for every dialog instead of the one picked automatically. Must
+/// be called before the first dialog, failing with `NFDError::AlreadyInitialized`
+/// afterwards, and with `NFDError::Unsupported` if `backend` isn't one of
+/// `available_backends`.
+///
+/// Overriding only means something where a platform has several backends to
+/// choose from, such as GTK and xdg-desktop-portal on Linux. The bundled
+/// nativefiledialog links exactly one backend on every platform, so for now the
+/// only backend that can be preferred is the one `backend` already returns.
+pub fn set_preferred_backend(backend: Backend) -> Result<()> {
+    let mut preferred = PREFERRED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if DIALOG_SHOWN.load(Ordering::SeqCst) {
+        return Err(NFDError::AlreadyInitialized);
+    }
+    if !available_backends().contains(&backend) {
+        return Err(NFDError::Unsupported(format!("the {:?} backend", backend)));
+    }
+    *preferred = Some(backend);
+    Ok(())
+}
+
+/// Note that a dialog is being shown, fixing the backend
+pub(crate) fn mark_dialog_shown() {
+    let _preferred = PREFERRED.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    DIALOG_SHOWN.store(true, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One test, since nothing can unmark a shown dialog
+    #[test]
+    fn preferred_backend() {
+        let available = available_backends();
+        let all = [Backend::Cocoa, Backend::Windows, Backend::Gtk, Backend::Simulated, Backend::Unsupported];
+        for &other in all.iter().filter(|backend| !available.contains(backend)) {
+            assert!(matches!(set_preferred_backend(other), Err(NFDError::Unsupported(_))), "{:?}", other);
+        }
+        for &backend in &available {
+            set_preferred_backend(backend).unwrap();
+            assert_eq!(super::backend(), backend);
+        }
+
+        mark_dialog_shown();
+        for &backend in &all {
+            assert!(matches!(set_preferred_backend(backend), Err(NFDError::AlreadyInitialized)), "{:?}", backend);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use backend::{self, Backend};
+use {DialogBuilder, NFDError, Response, Result};
+
+/// How often a waiting caller checks whether it should give up
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Fail with `NFDError::Unsupported` where dialogs can't run off the main thread,
+/// i.e. with Cocoa, whose panels crash the process if shown from another thread
+pub fn check_supported() -> Result<()> {
+    if backend::backend() == Backend::Cocoa {
+        Err(NFDError::Unsupported("dialogs off the main thread on macOS".to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Open `builder`'s dialog on a new thread, sending the result to the returned receiver
+pub fn spawn(builder: DialogBuilder<'static>) -> Receiver<Result<Response>> {
+    let (sender, receiver) = mpsc::channel();
+    spawn_to(builder, sender);
+    receiver
+}
+
+/// Open `builder`'s dialog on a new thread, sending the result to `sender`
+pub fn spawn_to(builder: DialogBuilder<'static>, sender: Sender<Result<Response>>) {
+    thread::spawn(move || {
+        // The caller may have stopped listening, that's fine
+        let _ = sender.send(builder.open());
+    });
+}
+
+/// Wait for the result from `spawn`, or `None` as soon as `give_up` returns true.
+/// The dialog thread is left running in that case.
+pub fn wait<F: FnMut() -> bool>(receiver: Receiver<Result<Response>>, mut give_up: F) -> Option<Result<Response>> {
+    loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(result) => return Some(result),
+            Err(RecvTimeoutError::Timeout) => {
+                if give_up() {
+                    return None;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Some(Err(NFDError::Error("the dialog thread panicked".to_owned())));
+            }
+        }
+    }
+}
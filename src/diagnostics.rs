@@ -0,0 +1,59 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::ffi::CStr;
+use std::sync::Mutex;
+
+use backend::{backend, Backend};
+use DialogType;
+
+/// Everything known about a dialog that failed in the backend
+#[derive(Clone, Debug)]
+pub struct DialogDiagnostics {
+    pub dialog_type: DialogType,
+    /// The filter list as handed to NFD
+    pub filter: Option<String>,
+    /// The default path as handed to NFD, after falling back to an existing parent
+    pub default_path: Option<String>,
+    pub backend: Backend,
+    /// The message from `NFD_GetError`
+    pub error: String,
+}
+
+static LAST: Mutex<Option<DialogDiagnostics>> = Mutex::new(None);
+
+/// Diagnostics for the most recent dialog that failed with `NFDError::Backend`,
+/// from any thread. `None` if no dialog has failed yet.
+pub fn last_diagnostics() -> Option<DialogDiagnostics> {
+    LAST.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+pub(crate) fn record(filter_list: Option<&CStr>, default_path: Option<&CStr>, dialog_type: DialogType, error: &str) {
+    let diagnostics = DialogDiagnostics {
+        dialog_type,
+        filter: filter_list.map(|filter| filter.to_string_lossy().into_owned()),
+        default_path: default_path.map(|path| path.to_string_lossy().into_owned()),
+        backend: backend(),
+        error: error.to_owned(),
+    };
+    *LAST.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(diagnostics);
+}
@@ -1,6 +1,8 @@
 use std::ffi;
 use std::fmt;
+use std::io;
 use std::str;
+use std::path::PathBuf;
 use std::error::Error;
 
 #[derive(Debug)]
@@ -8,6 +10,45 @@ pub enum NFDError {
     NulError(ffi::NulError),
     Utf8Error(str::Utf8Error),
     Error(String),
+    Io(io::Error),
+    /// The filter list is malformed; `offset` is the byte position of the offending token
+    InvalidFilter { detail: String, offset: usize },
+    /// Another dialog is still open; native dialogs can't be shown concurrently
+    DialogAlreadyOpen,
+    /// The MIME type isn't in the built-in table
+    UnknownMime { mime: String },
+    /// A selected path isn't under the base given to `relative_to`
+    OutsideBase { path: PathBuf, base: PathBuf },
+    /// The dialog or option isn't available on this platform or backend
+    Unsupported(String),
+    /// The caller stopped waiting for the dialog, which may still be on screen
+    Cancelled,
+    /// The dialog outlived the builder's `timeout`, and may still be on screen
+    Timeout,
+    /// With `strict_save_validation`, the extension of the default name isn't one
+    /// of the `allowed` extensions of the filter
+    ExtensionMismatch { name_ext: String, allowed: Vec<String> },
+    /// Another process holds the lock on the file chosen for `open_exclusive`
+    Locked { path: PathBuf },
+    /// With `SaveMode::ExistingOnly`, the chosen path isn't an existing file
+    MustExist { path: PathBuf },
+    /// The builder's `reject` predicate matched every selected path
+    AllRejected,
+    /// `require_version` asked for a newer nativefiledialog than the one linked in
+    VersionTooOld { found: (u32, u32), required: (u32, u32) },
+    /// The builder's `validate` check refused a selected path
+    ValidationFailed { reason: String },
+    /// No file in `dir` matches the filter: with `warn_empty_directory`, `dir` is
+    /// the default directory, with `rust_side_filter` the one the selection was
+    /// made in
+    NoMatchingFiles { dir: PathBuf },
+    /// `set_preferred_backend` was called after a dialog had already been shown
+    AlreadyInitialized,
+    /// The native dialog failed. `code` is the result NFD returned (`NFD_ERROR`),
+    /// `platform_code` the HRESULT or `NSError` code behind it where the backend
+    /// reports one (the bundled backends don't, so it is `None`), and `message` is
+    /// `NFD_GetError`'s description
+    Backend { code: i32, platform_code: Option<i64>, message: String },
 }
 
 impl fmt::Display for NFDError {
@@ -16,6 +57,34 @@ impl fmt::Display for NFDError {
             NFDError::NulError(ref err) => err.fmt(f),
             NFDError::Error(ref err) => err.fmt(f),
             NFDError::Utf8Error(ref err) => err.fmt(f),
+            NFDError::Io(ref err) => err.fmt(f),
+            NFDError::InvalidFilter { ref detail, offset } => {
+                write!(f, "invalid filter at byte {}: {}", offset, detail)
+            }
+            NFDError::DialogAlreadyOpen => f.write_str("a file dialog is already open"),
+            NFDError::UnknownMime { ref mime } => write!(f, "unknown MIME type `{}`", mime),
+            NFDError::Unsupported(ref what) => write!(f, "unsupported: {}", what),
+            NFDError::Cancelled => f.write_str("stopped waiting for the file dialog"),
+            NFDError::Timeout => f.write_str("timed out waiting for the file dialog"),
+            NFDError::ExtensionMismatch { ref name_ext, ref allowed } => {
+                write!(f, "default name has extension `{}`, but the filter only allows {}", name_ext, allowed.join(", "))
+            }
+            NFDError::Locked { ref path } => write!(f, "{} is locked by another process", path.display()),
+            NFDError::MustExist { ref path } => write!(f, "{} doesn't exist", path.display()),
+            NFDError::AllRejected => f.write_str("every selected path was rejected"),
+            NFDError::ValidationFailed { ref reason } => write!(f, "invalid selection: {}", reason),
+            NFDError::NoMatchingFiles { ref dir } => write!(f, "no file in {} matches the filter", dir.display()),
+            NFDError::AlreadyInitialized => f.write_str("the backend can't be changed once a dialog has been shown"),
+            NFDError::VersionTooOld { found, required } => {
+                write!(f, "nativefiledialog {}.{} is linked, but {}.{} is required", found.0, found.1, required.0, required.1)
+            }
+            NFDError::Backend { ref message, platform_code: Some(platform_code), .. } => {
+                write!(f, "{} (platform code {:#x})", message, platform_code)
+            }
+            NFDError::Backend { ref message, .. } => f.write_str(message),
+            NFDError::OutsideBase { ref path, ref base } => {
+                write!(f, "{} is not under {}", path.display(), base.display())
+            }
         }
     }
 }
@@ -34,3 +103,9 @@ impl From<str::Utf8Error> for NFDError {
     }
 }
 
+impl From<io::Error> for NFDError {
+    fn from(err: io::Error) -> NFDError {
+        NFDError::Io(err)
+    }
+}
+
@@ -49,6 +49,7 @@ pub enum nfdresult_t {
     NFD_OKAY = 1,
     NFD_CANCEL = 2,
 }
+#[cfg(any(unix, windows))]
 extern "C" {
     pub fn NFD_OpenDialog(filterList: *const nfdchar_t, defaultPath: *const nfdchar_t, outPath: *mut *mut nfdchar_t) -> nfdresult_t;
     pub fn NFD_OpenDialogMultiple(filterList: *const nfdchar_t, defaultPath: *const nfdchar_t, outPaths: *mut nfdpathset_t) -> nfdresult_t;
@@ -59,3 +60,60 @@ extern "C" {
     pub fn NFD_PathSet_GetPath(pathSet: *const nfdpathset_t, index: size_t) -> *mut nfdchar_t;
     pub fn NFD_PathSet_Free(pathSet: *mut nfdpathset_t);
 }
+
+// From glib, which build.rs links for the GTK backend
+#[cfg(all(unix, not(target_vendor = "apple")))]
+extern "C" {
+    pub fn g_set_prgname(prgname: *const raw::c_char);
+    pub fn g_get_home_dir() -> *const raw::c_char;
+    pub fn g_get_user_special_dir(directory: GUserDirectory) -> *const raw::c_char;
+}
+
+#[cfg(all(unix, not(target_vendor = "apple")))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+#[derive(Debug)]
+pub enum GUserDirectory {
+    G_USER_DIRECTORY_DESKTOP = 0,
+    G_USER_DIRECTORY_DOCUMENTS = 1,
+    G_USER_DIRECTORY_DOWNLOAD = 2,
+    G_USER_DIRECTORY_MUSIC = 3,
+    G_USER_DIRECTORY_PICTURES = 4,
+    G_USER_DIRECTORY_PUBLIC_SHARE = 5,
+    G_USER_DIRECTORY_TEMPLATES = 6,
+    G_USER_DIRECTORY_VIDEOS = 7,
+}
+
+// From shell32 and ole32, which build.rs links for the Windows backend
+#[cfg(windows)]
+#[repr(C)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct GUID(pub u32, pub u16, pub u16, pub [u8; 8]);
+
+#[cfg(windows)]
+extern "system" {
+    pub fn SHGetKnownFolderPath(rfid: *const GUID, dwFlags: u32, hToken: *mut raw::c_void, ppszPath: *mut *mut u16) -> i32;
+    pub fn CoTaskMemFree(pv: *mut raw::c_void);
+}
+
+// Targets that are neither unix nor windows (wasm, some embedded and exotic
+// targets) have no nativefiledialog backend, so build.rs compiles nothing and
+// these stand-ins keep the crate linking. Dialogs fail with `NFDError::Unsupported`.
+#[cfg(not(any(unix, windows)))]
+pub use self::unsupported::*;
+
+#[cfg(not(any(unix, windows)))]
+mod unsupported {
+    use super::*;
+
+    const ERROR: &[u8] = b"nativefiledialog is not available on this target\0";
+
+    pub unsafe fn NFD_OpenDialog(_: *const nfdchar_t, _: *const nfdchar_t, _: *mut *mut nfdchar_t) -> nfdresult_t { nfdresult_t::NFD_ERROR }
+    pub unsafe fn NFD_OpenDialogMultiple(_: *const nfdchar_t, _: *const nfdchar_t, _: *mut nfdpathset_t) -> nfdresult_t { nfdresult_t::NFD_ERROR }
+    pub unsafe fn NFD_SaveDialog(_: *const nfdchar_t, _: *const nfdchar_t, _: *mut *mut nfdchar_t) -> nfdresult_t { nfdresult_t::NFD_ERROR }
+    pub unsafe fn NFD_PickFolder(_: *const nfdchar_t, _: *mut *mut nfdchar_t) -> nfdresult_t { nfdresult_t::NFD_ERROR }
+    pub unsafe fn NFD_GetError() -> *const raw::c_char { ERROR.as_ptr() as *const raw::c_char }
+    pub unsafe fn NFD_PathSet_GetCount(_: *const nfdpathset_t) -> size_t { 0 }
+    pub unsafe fn NFD_PathSet_GetPath(_: *const nfdpathset_t, _: size_t) -> *mut nfdchar_t { ::std::ptr::null_mut() }
+    pub unsafe fn NFD_PathSet_Free(_: *mut nfdpathset_t) {}
+}
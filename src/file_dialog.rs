@@ -0,0 +1,97 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use {open_dialog, DialogType, Response, Result};
+
+/// Everything a `FileDialog` needs to know to show a dialog
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialogConfig {
+    /// Filter list in NFD syntax, e.g. `"png,jpg;pdf"`
+    pub filter: Option<String>,
+    pub default_path: Option<String>,
+    pub dialog_type: DialogType,
+}
+
+impl DialogConfig {
+    pub fn new(dialog_type: DialogType) -> DialogConfig {
+        DialogConfig {
+            filter: None,
+            default_path: None,
+            dialog_type,
+        }
+    }
+}
+
+/// Something that can show a file dialog.
+///
+/// Code that takes a `&dyn FileDialog` instead of calling `open_dialog` directly
+/// can be handed a `MockDialog` in its tests.
+pub trait FileDialog {
+    fn open(&self, cfg: &DialogConfig) -> Result<Response>;
+}
+
+/// The real dialog, shown through `open_dialog`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NativeDialog;
+
+impl FileDialog for NativeDialog {
+    fn open(&self, cfg: &DialogConfig) -> Result<Response> {
+        open_dialog(cfg.filter.as_deref(), cfg.default_path.as_deref(), cfg.dialog_type)
+    }
+}
+
+/// A fake dialog that hands back queued responses and records how it was opened.
+///
+/// Responses are returned in the order they were queued; once the queue runs dry
+/// every further dialog is cancelled.
+#[derive(Default)]
+pub struct MockDialog {
+    responses: Mutex<VecDeque<Result<Response>>>,
+    calls: Mutex<Vec<DialogConfig>>,
+}
+
+impl MockDialog {
+    pub fn new() -> MockDialog {
+        MockDialog::default()
+    }
+
+    /// Queue the result of the next dialog
+    pub fn respond(&self, response: Result<Response>) -> &MockDialog {
+        self.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    /// The configurations the dialog has been opened with so far, oldest first
+    pub fn calls(&self) -> Vec<DialogConfig> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl FileDialog for MockDialog {
+    fn open(&self, cfg: &DialogConfig) -> Result<Response> {
+        self.calls.lock().unwrap().push(cfg.clone());
+        self.responses.lock().unwrap().pop_front().unwrap_or(Ok(Response::Cancel))
+    }
+}
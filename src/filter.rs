@@ -0,0 +1,317 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::fmt;
+
+use error::NFDError;
+use Result;
+
+/// Longest extension the native layer has room for (`NFD_MAX_STRLEN` less the `*.`)
+const MAX_EXTENSION_LEN: usize = 256 - 3;
+
+/// Split an NFD filter list such as `"png,jpg;pdf"` into its groups of extensions.
+///
+/// Groups are separated by `;` and the extensions within a group by `,`. An empty
+/// list means "no filter" and yields no groups. Empty groups, empty extensions and
+/// extensions containing whitespace are rejected with `NFDError::InvalidFilter`,
+/// whose `offset` is the byte position of the offending token.
+pub fn parse(filter: &str) -> Result<Vec<Vec<&str>>> {
+    let mut groups = Vec::new();
+    if filter.is_empty() {
+        return Ok(groups);
+    }
+
+    let mut offset = 0;
+    for group in filter.split(';') {
+        if group.is_empty() {
+            return Err(invalid("empty filter group", offset));
+        }
+
+        let mut extensions = Vec::new();
+        for extension in group.split(',') {
+            if let Some(err) = check_extension(group, extension) {
+                return Err(invalid(err.to_string(), offset));
+            }
+            extensions.push(extension);
+            offset += extension.len() + 1;
+        }
+        groups.push(extensions);
+    }
+
+    Ok(groups)
+}
+
+/// A filter list made of named groups of extensions.
+///
+/// NFD's filter syntax has no room for group names, so they are only shown by
+/// backends that support them. `to_string()` gives the list in NFD syntax.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    groups: Vec<FilterGroup>,
+}
+
+/// One group of a `Filter`, e.g. "Images" accepting `png` and `jpg`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterGroup {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// A problem with one group of a `Filter`, as reported by `Filter::build`
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterError {
+    /// The group has no extensions
+    EmptyGroup { group: String },
+    /// An extension is empty, too long, or contains whitespace or a separator
+    InvalidExtension { group: String, extension: String, reason: &'static str },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FilterError::EmptyGroup { ref group } => {
+                write!(f, "filter group `{}` has no extensions", group)
+            }
+            FilterError::InvalidExtension { ref group, ref extension, reason } => {
+                write!(f, "extension `{}` in group `{}` {}", extension, group, reason)
+            }
+        }
+    }
+}
+
+impl ::std::error::Error for FilterError {}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    /// A filter with one group per file association the app registers with the OS,
+    /// given as `(type name, extensions)`, e.g. `("PNG image", &[".png"])`, so the
+    /// dialog offers exactly the types the app opens. Leading dots are dropped, as
+    /// association tables usually spell extensions with one. Not validated, as with
+    /// `group`.
+    ///
+    /// Apps that declare their associations in a manifest (an Info.plist, a
+    /// `.desktop` file, an MSIX manifest) can keep the two in sync by having their
+    /// build script read the manifest and write the table out as Rust, to be
+    /// `include!`d here; nfd doesn't ship such a build script.
+    pub fn from_associations(associations: &[(&str, &[&str])]) -> Filter {
+        associations.iter().fold(Filter::new(), |filter, &(name, extensions)| {
+            let extensions: Vec<&str> = extensions.iter().map(|ext| ext.trim_start_matches('.')).collect();
+            filter.group(name, &extensions)
+        })
+    }
+
+    /// Add a group without validating it; bad extensions are caught by `build`,
+    /// or when the dialog opens
+    pub fn group(mut self, name: &str, extensions: &[&str]) -> Filter {
+        self.groups.push(FilterGroup {
+            name: name.to_owned(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Add a group, failing straight away if it is empty or an extension is malformed
+    pub fn try_group(self, name: &str, extensions: &[&str]) -> Result<Filter> {
+        let mut offset = self.to_string().len();
+        if !self.groups.is_empty() {
+            offset += 1;
+        }
+        if extensions.is_empty() {
+            let err = FilterError::EmptyGroup { group: name.to_owned() };
+            return Err(invalid(err.to_string(), offset));
+        }
+        for extension in extensions {
+            if let Some(err) = check_extension(name, extension) {
+                return Err(invalid(err.to_string(), offset));
+            }
+            offset += extension.len() + 1;
+        }
+        Ok(self.group(name, extensions))
+    }
+
+    /// Validate every group at once, returning all the problems found rather than
+    /// only the first one
+    pub fn build(self) -> ::std::result::Result<Filter, Vec<FilterError>> {
+        let mut errors = Vec::new();
+        for group in &self.groups {
+            if group.extensions.is_empty() {
+                errors.push(FilterError::EmptyGroup { group: group.name.clone() });
+            }
+            errors.extend(group.extensions.iter().filter_map(|ext| check_extension(&group.name, ext)));
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn groups(&self) -> &[FilterGroup] {
+        &self.groups
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, group) in self.groups.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            f.write_str(&group.extensions.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a `Filter` from named groups of extensions, checking the extensions
+/// while compiling:
+///
+/// ```ignore
+/// let filter = filter!("Images" => ["png", "jpg"], "Docs" => ["pdf"]);
+/// ```
+///
+/// Each group is a name (any `&str` expression) followed by `=>` and a
+/// non-empty list of string literals. An empty extension, one containing `,`,
+/// `;` or ASCII whitespace, or one too long for the native layer fails to
+/// compile with error E0080 and a message such as "filter! extension contains a
+/// separator", pointing at the macro call. An empty group is a
+/// syntax error ("unexpected end of macro invocation"). Non-ASCII whitespace is
+/// only caught when the dialog opens, as with `Filter::group`.
+///
+/// On the 2015 edition the macro needs `#[macro_use] extern crate nfd;`.
+#[macro_export]
+macro_rules! filter {
+    ($($name:expr => [$($extension:literal),+ $(,)?]),+ $(,)?) => {{
+        $($(
+            const _: () = $crate::__check_filter_extension($extension);
+        )+)+
+        $crate::Filter::new()$(.group($name, &[$($extension),+]))+
+    }};
+}
+
+/// The compile time half of `filter!`: panics, failing const evaluation, if
+/// `extension` would be rejected by `check_extension`
+#[doc(hidden)]
+pub const fn check_extension_const(extension: &str) {
+    let bytes = extension.as_bytes();
+    if bytes.is_empty() {
+        panic!("filter! extension is empty");
+    }
+    if bytes.len() > MAX_EXTENSION_LEN {
+        panic!("filter! extension is too long");
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b',' | b';' => panic!("filter! extension contains a separator"),
+            b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c' => panic!("filter! extension contains whitespace"),
+            _ => (),
+        }
+        i += 1;
+    }
+}
+
+fn check_extension(group: &str, extension: &str) -> Option<FilterError> {
+    let reason = if extension.is_empty() {
+        "is empty"
+    } else if extension.contains([',', ';']) {
+        "contains a separator"
+    } else if extension.contains(char::is_whitespace) {
+        "contains whitespace"
+    } else if extension.len() > MAX_EXTENSION_LEN {
+        "is too long"
+    } else {
+        return None;
+    };
+
+    Some(FilterError::InvalidExtension {
+        group: group.to_owned(),
+        extension: extension.to_owned(),
+        reason,
+    })
+}
+
+fn invalid<S: Into<String>>(detail: S, offset: usize) -> NFDError {
+    NFDError::InvalidFilter {
+        detail: detail.into(),
+        offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The offset `parse` reports for a malformed filter
+    fn error_offset(filter: &str) -> usize {
+        match parse(filter) {
+            Err(NFDError::InvalidFilter { offset, .. }) => offset,
+            other => panic!("{:?} parsed as {:?}", filter, other),
+        }
+    }
+
+    #[test]
+    fn parses_groups() {
+        assert_eq!(parse("").unwrap(), Vec::<Vec<&str>>::new());
+        assert_eq!(parse("png,jpg;pdf").unwrap(), vec![vec!["png", "jpg"], vec!["pdf"]]);
+    }
+
+    #[test]
+    fn rejects_empty_extension() {
+        assert_eq!(error_offset("png,,jpg"), 4);
+        assert_eq!(error_offset(",png"), 0);
+        assert_eq!(error_offset("png,"), 4);
+    }
+
+    #[test]
+    fn rejects_empty_group() {
+        assert_eq!(error_offset(";"), 0);
+        assert_eq!(error_offset("png;"), 4);
+        assert_eq!(error_offset("png;;pdf"), 4);
+    }
+
+    #[test]
+    fn rejects_whitespace() {
+        assert_eq!(error_offset("png;j pg"), 4);
+        assert_eq!(error_offset("png, jpg"), 4);
+        assert_eq!(error_offset("png,jpg;pdf\t"), 8);
+    }
+
+    #[test]
+    fn rejects_long_extension() {
+        assert_eq!(error_offset(&"x".repeat(MAX_EXTENSION_LEN + 1)), 0);
+        assert!(parse(&"x".repeat(MAX_EXTENSION_LEN)).is_ok());
+    }
+
+    #[test]
+    fn filter_macro() {
+        let filter = filter!("Images" => ["png", "jpg"], "Docs" => ["pdf"]);
+        let names: Vec<&str> = filter.groups().iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, ["Images", "Docs"]);
+        assert_eq!(filter.to_string(), "png,jpg;pdf");
+        assert_eq!(filter!("Images" => ["png", "jpg",],).to_string(), "png,jpg");
+    }
+}
@@ -0,0 +1,113 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "serialize_calls")]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "serialize_calls")]
+use std::thread::{self, ThreadId};
+
+use error::NFDError;
+use Result;
+
+static DIALOG_OPEN: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "serialize_calls")]
+static SERIALIZE: Mutex<()> = Mutex::new(());
+
+/// The thread holding `SERIALIZE`, so that it fails instead of waiting on itself
+#[cfg(feature = "serialize_calls")]
+static OWNER: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// Held for as long as a native dialog is showing.
+///
+/// Native dialogs are not reentrant, so a second dialog opened while one is up
+/// fails with `NFDError::DialogAlreadyOpen`. With the `serialize_calls` feature
+/// the second caller instead blocks until the first dialog closes; if that
+/// dialog is never closed, every later dialog waits forever. A dialog opened from
+/// the thread already showing one, e.g. from a callback, still fails, since it
+/// would wait for itself.
+pub struct DialogGuard {
+    #[cfg(feature = "serialize_calls")]
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl DialogGuard {
+    pub fn acquire() -> Result<DialogGuard> {
+        #[cfg(feature = "serialize_calls")]
+        let lock = {
+            let current = thread::current().id();
+            if *owner() == Some(current) {
+                return Err(NFDError::DialogAlreadyOpen);
+            }
+            let lock = SERIALIZE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            *owner() = Some(current);
+            lock
+        };
+
+        if DIALOG_OPEN.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+            return Err(NFDError::DialogAlreadyOpen);
+        }
+
+        Ok(DialogGuard {
+            #[cfg(feature = "serialize_calls")]
+            _lock: lock,
+        })
+    }
+}
+
+/// Whether a dialog is showing anywhere in this process, e.g. to disable menu
+/// items that would open another one
+pub fn is_dialog_open() -> bool {
+    DIALOG_OPEN.load(Ordering::SeqCst)
+}
+
+#[cfg(feature = "serialize_calls")]
+fn owner() -> MutexGuard<'static, Option<ThreadId>> {
+    OWNER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Drop for DialogGuard {
+    fn drop(&mut self) {
+        // Before `_lock` is released, so the next owner isn't cleared
+        #[cfg(feature = "serialize_calls")]
+        {
+            *owner() = None;
+        }
+        DIALOG_OPEN.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reentry_fails() {
+        let guard = DialogGuard::acquire().unwrap();
+        assert!(is_dialog_open());
+        assert!(matches!(DialogGuard::acquire(), Err(NFDError::DialogAlreadyOpen)));
+        drop(guard);
+        assert!(!is_dialog_open());
+        drop(DialogGuard::acquire().unwrap());
+    }
+}
@@ -0,0 +1,193 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::env;
+use std::path::PathBuf;
+
+/// A standard per-user folder, resolved with the platform's own lookup
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum KnownFolder {
+    Home,
+    Documents,
+    Downloads,
+    Desktop,
+    Pictures,
+    /// The temporary directory, as `std::env::temp_dir`
+    Temp,
+}
+
+impl KnownFolder {
+    /// Where this folder is for the current user, or `None` if the platform doesn't
+    /// say or it doesn't exist.
+    ///
+    /// Windows asks `SHGetKnownFolderPath`, so redirected folders are found. GTK
+    /// targets ask glib, which reads the XDG user dirs (`~/.config/user-dirs.dirs`).
+    /// macOS uses the standard folders in the home directory.
+    pub fn path(self) -> Option<PathBuf> {
+        let path = match self {
+            KnownFolder::Temp => Some(env::temp_dir()),
+            folder => platform::resolve(folder),
+        };
+        path.filter(|path| path.is_dir())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::PathBuf;
+    use std::ptr;
+
+    use super::KnownFolder;
+    use ffi::*;
+
+    const FOLDERID_PROFILE: GUID = GUID(0x5e6c858f, 0x0e22, 0x4760, [0x9a, 0xfe, 0xea, 0x33, 0x17, 0xb6, 0x71, 0x73]);
+    const FOLDERID_DOCUMENTS: GUID = GUID(0xfdd39ad0, 0x238f, 0x46af, [0xad, 0xb4, 0x6c, 0x85, 0x48, 0x03, 0x69, 0xc7]);
+    const FOLDERID_DOWNLOADS: GUID = GUID(0x374de290, 0x123f, 0x4565, [0x91, 0x64, 0x39, 0xc4, 0x92, 0x5e, 0x46, 0x7b]);
+    const FOLDERID_DESKTOP: GUID = GUID(0xb4bfcc3a, 0xdb2c, 0x424c, [0xb0, 0x29, 0x7f, 0xe9, 0x9a, 0x87, 0xc6, 0x41]);
+    const FOLDERID_PICTURES: GUID = GUID(0x33e28130, 0x4e1e, 0x4676, [0x83, 0x5a, 0x98, 0x39, 0x5c, 0x3b, 0xc3, 0xbb]);
+
+    pub fn resolve(folder: KnownFolder) -> Option<PathBuf> {
+        let id = match folder {
+            KnownFolder::Home => &FOLDERID_PROFILE,
+            KnownFolder::Documents => &FOLDERID_DOCUMENTS,
+            KnownFolder::Downloads => &FOLDERID_DOWNLOADS,
+            KnownFolder::Desktop => &FOLDERID_DESKTOP,
+            KnownFolder::Pictures => &FOLDERID_PICTURES,
+            KnownFolder::Temp => return None,
+        };
+
+        unsafe {
+            let mut path = ptr::null_mut();
+            let result = SHGetKnownFolderPath(id, 0, ptr::null_mut(), &mut path);
+            // The buffer has to be freed even when the call fails
+            let resolved = if result == 0 && !path.is_null() {
+                let len = (0..).take_while(|&i| *path.offset(i) != 0).count();
+                let wide = ::std::slice::from_raw_parts(path, len);
+                Some(PathBuf::from(OsString::from_wide(wide)))
+            } else {
+                None
+            };
+            CoTaskMemFree(path as *mut _);
+            resolved
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_vendor = "apple")))]
+mod platform {
+    use std::ffi::CStr;
+    use std::path::PathBuf;
+
+    use super::KnownFolder;
+    use ffi::*;
+
+    pub fn resolve(folder: KnownFolder) -> Option<PathBuf> {
+        let directory = match folder {
+            KnownFolder::Home => return path_from(unsafe { g_get_home_dir() }),
+            KnownFolder::Documents => GUserDirectory::G_USER_DIRECTORY_DOCUMENTS,
+            KnownFolder::Downloads => GUserDirectory::G_USER_DIRECTORY_DOWNLOAD,
+            KnownFolder::Desktop => GUserDirectory::G_USER_DIRECTORY_DESKTOP,
+            KnownFolder::Pictures => GUserDirectory::G_USER_DIRECTORY_PICTURES,
+            KnownFolder::Temp => return None,
+        };
+        path_from(unsafe { g_get_user_special_dir(directory) })
+    }
+
+    /// glib owns the returned strings, they must not be freed
+    fn path_from(path: *const ::std::os::raw::c_char) -> Option<PathBuf> {
+        if path.is_null() {
+            None
+        } else {
+            unsafe { CStr::from_ptr(path) }.to_str().ok().map(PathBuf::from)
+        }
+    }
+}
+
+#[cfg(not(any(windows, all(unix, not(target_vendor = "apple")))))]
+mod platform {
+    use std::env;
+    use std::path::PathBuf;
+
+    use super::KnownFolder;
+
+    pub fn resolve(folder: KnownFolder) -> Option<PathBuf> {
+        let home = PathBuf::from(env::var_os("HOME")?);
+        Some(match folder {
+            KnownFolder::Home => home,
+            KnownFolder::Documents => home.join("Documents"),
+            KnownFolder::Downloads => home.join("Downloads"),
+            KnownFolder::Desktop => home.join("Desktop"),
+            KnownFolder::Pictures => home.join("Pictures"),
+            KnownFolder::Temp => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_is_temp_dir() {
+        assert_eq!(KnownFolder::Temp.path(), Some(env::temp_dir()));
+    }
+
+    #[test]
+    fn resolved_folders_are_existing_absolute_dirs() {
+        let folders = [KnownFolder::Home, KnownFolder::Documents, KnownFolder::Downloads, KnownFolder::Desktop, KnownFolder::Pictures];
+        for &folder in &folders {
+            if let Some(path) = folder.path() {
+                assert!(path.is_absolute() && path.is_dir(), "{:?} resolved to {}", folder, path.display());
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_home_is_profile() {
+        let profile = env::var_os("USERPROFILE").map(PathBuf::from);
+        assert_eq!(platform::resolve(KnownFolder::Home), profile);
+    }
+
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    #[test]
+    fn gtk_home_is_home() {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        if home.is_some() {
+            assert_eq!(platform::resolve(KnownFolder::Home), home);
+        }
+    }
+
+    #[cfg(not(any(windows, all(unix, not(target_vendor = "apple")))))]
+    #[test]
+    fn standard_folders_are_in_home() {
+        let home = match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home),
+            None => return,
+        };
+        assert_eq!(platform::resolve(KnownFolder::Home), Some(home.clone()));
+        assert_eq!(platform::resolve(KnownFolder::Documents), Some(home.join("Documents")));
+        assert_eq!(platform::resolve(KnownFolder::Pictures), Some(home.join("Pictures")));
+    }
+}
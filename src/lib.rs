@@ -20,35 +20,202 @@
    THE SOFTWARE.
 */
 
+#[macro_use]
+extern crate log;
+#[cfg(feature = "async-std")]
+extern crate async_std as async_std_crate;
+#[cfg(feature = "lock")]
+extern crate fs2;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+
+#[cfg(feature = "async-std")]
+pub mod async_std;
+mod background;
+mod backend;
 mod ffi;
 mod error;
+mod escape;
+mod file_dialog;
+mod filter;
+mod diagnostics;
+mod guard;
+mod known_folder;
+mod memory;
+mod mime;
+mod pathset;
+mod paths;
+mod pool;
+pub mod prelude;
+mod replay;
+mod response;
+#[cfg(feature = "rfd-compat")]
+pub mod rfd_compat;
+mod sandbox;
+mod session;
+mod state;
+mod uri;
+mod utf8;
+#[cfg(feature = "integration-sim")]
+pub mod sim;
 
 use ffi::*;
+use std::borrow::Cow;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
-use error::NFDError;
-
-/// Result of opening a file dialog
-pub enum Response {
-    /// User pressed okay. `String` is the file path selected
-    Okay(String),
-    /// User pressed okay on mupliple selections. Result contains a Vec of all the files
-    OkayMultiple(Vec<String>),
-    /// User pressed cancel
-    Cancel,
-}
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use guard::DialogGuard;
+use memory::DirectoryMemory;
+#[cfg(feature = "integration-sim")]
+use sim::run as run_backend;
+#[cfg(not(feature = "integration-sim"))]
+use run_native as run_backend;
+
+pub use backend::{available_backends, backend, set_preferred_backend, Backend};
+pub use diagnostics::{last_diagnostics, DialogDiagnostics};
+pub use error::NFDError;
+pub use guard::is_dialog_open;
+pub use known_folder::KnownFolder;
+pub use file_dialog::{DialogConfig, FileDialog, MockDialog, NativeDialog};
+pub use filter::{Filter, FilterError, FilterGroup};
+#[doc(hidden)]
+pub use filter::check_extension_const as __check_filter_extension;
+pub use pathset::PathSet;
+pub use replay::{RecordingDialog, ReplayDialog, ReplayMatch};
+pub use response::{Outcome, Response};
+pub use sandbox::is_sandboxed;
+pub use session::DialogSession;
+pub use state::set_state_file;
 
-#[derive(Copy, Clone, PartialEq)]
+/// Kind of dialog to open.
+///
+/// The folder pickers return directories rather than files; they are not open
+/// dialogs restricted to folders.
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum DialogType {
+    /// Open one existing file
     SingleFile,
+    /// Open one or more existing files
     MultipleFiles,
+    /// Choose a path to save to
     SaveFile,
+    /// Choose exactly one folder
     PickFolder,
+    /// Choose one or more folders. Not supported by the bundled backends, where it
+    /// fails with `NFDError::Unsupported`
+    PickFolderMultiple,
+}
+
+impl DialogType {
+    fn is_multiple(self) -> bool {
+        self == DialogType::MultipleFiles || self == DialogType::PickFolderMultiple
+    }
+}
+
+/// How a save dialog treats the chosen file, see `DialogBuilder::save_mode`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SaveMode {
+    /// Save the document itself to the chosen path
+    Standard,
+    /// Save a copy, leaving the open document where it is (macOS "Save a Copy")
+    Copy,
+    /// Only save over a file that already exists, failing with `NFDError::MustExist`
+    /// otherwise
+    ExistingOnly,
+}
+
+/// The file chosen with `DialogBuilder::open_or_new`
+#[derive(Clone, Debug, PartialEq)]
+pub enum OpenOrNew {
+    /// A file that already exists, to open
+    Existing(PathBuf),
+    /// A name for a file that doesn't exist yet, to create
+    New(PathBuf),
+}
+
+/// Path separators in returned paths, see `DialogBuilder::normalize_separators`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SeparatorStyle {
+    /// As the platform returns them
+    Native,
+    /// `/` everywhere, e.g. `C:/Users/me/a.txt`
+    ForwardSlash,
 }
 
+/// Control focused when a dialog opens, see `DialogBuilder::focus`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FocusTarget {
+    /// The file name field
+    Filename,
+    /// The list of files
+    FileList,
+}
+
+/// A predicate over selected paths, see `DialogBuilder::reject`
+type PathPredicate = dyn Fn(&Path) -> bool + Send + Sync;
+
+/// A check on selected paths, see `DialogBuilder::validate`
+type PathValidator = dyn Fn(&Path) -> std::result::Result<(), String> + Send + Sync;
+
+/// Options for a file dialog, opened with one of the `open` methods.
+///
+/// Some options are only requests: the bundled nativefiledialog backends can't
+/// pass them on, so they are stored and logged at debug level when the dialog
+/// opens, but otherwise ignored. On every platform these are `default_name`,
+/// `follow_symlinks`, `show_hidden`, `show_preview`, `add_bookmark`, `preselect`,
+/// `focus`, `restore_focus`, `initial_size`, `maximized` and `SaveMode::Copy`.
+/// `strict_extensions` is only honoured on macOS, where `primary_type_hint` and
+/// `default_filter_index` are ignored instead.
 pub struct DialogBuilder<'a> {
-    filter: Option<&'a str>,
-    default_path: Option<&'a str>,
+    filter: Option<String>,
+    /// `effective_filter` converted for NFD, kept between opens until a filter
+    /// setter changes it
+    filter_cstring: OnceLock<Option<CString>>,
+    default_path: Option<Cow<'a, str>>,
+    default_name: Option<String>,
+    type_hint: Option<String>,
+    default_filter_index: Option<usize>,
+    suggestions: Vec<PathBuf>,
+    fallbacks: Vec<PathBuf>,
+    known_folder: Option<KnownFolder>,
+    cwd_context: Option<PathBuf>,
+    remember_as: Option<String>,
+    memory: Option<Arc<DirectoryMemory>>,
+    state_token: Option<String>,
+    force_default_path: bool,
+    canonicalize: bool,
+    force_extension: bool,
+    relative_to: Option<PathBuf>,
+    reject_outside_base: bool,
+    separators: SeparatorStyle,
+    reject: Option<Arc<PathPredicate>>,
+    validate: Option<Arc<PathValidator>>,
+    initial_size: Option<(u32, u32)>,
+    maximized: bool,
+    follow_symlinks: Option<bool>,
+    show_hidden: Option<bool>,
+    show_preview: Option<bool>,
+    bookmarks: Vec<(PathBuf, String)>,
+    preselect: Vec<PathBuf>,
+    save_mode: SaveMode,
+    focus: Option<FocusTarget>,
+    restore_focus: bool,
+    report_filter: bool,
+    report_last_dir: bool,
+    read_only: Option<bool>,
+    #[cfg(feature = "timing")]
+    timed: bool,
+    strict_save_validation: bool,
+    strict_extensions: bool,
+    warn_empty_directory: bool,
+    rust_side_filter: bool,
+    timeout: Option<Duration>,
+    dry_run: bool,
+    lossy_replacement: char,
     dialog_type: DialogType,
 }
 
@@ -57,26 +224,1253 @@ impl<'a> DialogBuilder<'a> {
     pub fn new(dialog_type: DialogType) -> Self {
         DialogBuilder {
             filter: None,
+            filter_cstring: OnceLock::new(),
             default_path: None,
+            default_name: None,
+            type_hint: None,
+            default_filter_index: None,
+            suggestions: Vec::new(),
+            fallbacks: Vec::new(),
+            known_folder: None,
+            cwd_context: None,
+            remember_as: None,
+            memory: None,
+            state_token: None,
+            force_default_path: false,
+            canonicalize: false,
+            force_extension: false,
+            relative_to: None,
+            reject_outside_base: false,
+            separators: SeparatorStyle::Native,
+            reject: None,
+            validate: None,
+            initial_size: None,
+            maximized: false,
+            follow_symlinks: None,
+            show_hidden: None,
+            show_preview: None,
+            bookmarks: Vec::new(),
+            preselect: Vec::new(),
+            save_mode: SaveMode::Standard,
+            focus: None,
+            restore_focus: false,
+            report_filter: false,
+            report_last_dir: false,
+            read_only: None,
+            #[cfg(feature = "timing")]
+            timed: false,
+            strict_save_validation: false,
+            strict_extensions: false,
+            warn_empty_directory: false,
+            rust_side_filter: false,
+            timeout: None,
+            dry_run: false,
+            lossy_replacement: char::REPLACEMENT_CHARACTER,
             dialog_type: dialog_type,
         }
     }
 
-    pub fn filter(&'a mut self, filter: &'a str) -> &mut DialogBuilder {
+    pub fn filter(&mut self, filter: &str) -> &mut DialogBuilder<'a> {
+        self.filter = Some(filter.to_owned());
+        self.filter_cstring.take();
+        self
+    }
+
+    /// Only accept files with the given extension, e.g. `ext("png")` or `ext(".png")`.
+    /// Fails with `NFDError::InvalidFilter` if there is no extension, e.g. `ext(".")`,
+    /// rather than opening the dialog without a filter.
+    pub fn ext(&mut self, extension: &str) -> Result<&mut DialogBuilder<'a>> {
+        let extension = extension.trim_start_matches('.');
+        let name = format!("{} files", extension.to_uppercase());
+        self.filter = Some(Filter::new().try_group(&name, &[extension])?.to_string());
+        self.filter_cstring.take();
+        Ok(self)
+    }
+
+    /// By-value form of `ext`
+    pub fn with_ext(mut self, extension: &str) -> Result<DialogBuilder<'a>> {
+        self.ext(extension)?;
+        Ok(self)
+    }
+
+    pub fn default_path(&mut self, path: &'a str) -> &mut DialogBuilder<'a> {
+        self.default_path = Some(Cow::Borrowed(path));
+        self
+    }
+
+    /// File name to suggest in a save dialog. Ignored by the bundled backends.
+    pub fn default_name(&mut self, name: &str) -> &mut DialogBuilder<'a> {
+        self.default_name = Some(name.to_owned());
+        self
+    }
+
+    /// Use the file name of `path`, without its directory, as the `default_name`,
+    /// e.g. to "Save As" a document that is already open. Leaves the name unset if
+    /// `path` has no file name component, such as `/` or `..`.
+    pub fn default_name_from(&mut self, path: &Path) -> &mut DialogBuilder<'a> {
+        if let Some(name) = path.file_name() {
+            self.default_name = Some(name.to_string_lossy().into_owned());
+        }
+        self
+    }
+
+    /// Set the filter from `(name, extensions)` groups, equivalent to building a
+    /// `Filter` but serialized straight into one `String` sized up front, with no
+    /// intermediate allocations. The `CString` handed to NFD is a second
+    /// allocation, made when the dialog first opens and kept for later opens.
+    /// Handy for static filters in dialogs that are opened often.
+    pub fn filter_parts(&mut self, groups: &[(&str, &[&str])]) -> &mut DialogBuilder<'a> {
+        let len = groups.iter()
+            .map(|&(_, extensions)| extensions.iter().map(|ext| ext.len() + 1).sum::<usize>())
+            .sum();
+        let mut filter = String::with_capacity(len);
+        for (i, &(_, extensions)) in groups.iter().enumerate() {
+            if i > 0 {
+                filter.push(';');
+            }
+            for (j, extension) in extensions.iter().enumerate() {
+                if j > 0 {
+                    filter.push(',');
+                }
+                filter.push_str(extension);
+            }
+        }
         self.filter = Some(filter);
+        self.filter_cstring.take();
+        self
+    }
+
+    /// Set the filter from MIME types such as `image/png` or `application/pdf`,
+    /// accepting the extensions of all of them in a single group
+    pub fn mime_filter(&mut self, mimes: &[&str]) -> Result<&mut DialogBuilder<'a>> {
+        let mut extensions = Vec::new();
+        for &mime in mimes {
+            match mime::extensions(mime) {
+                Some(known) => extensions.extend_from_slice(known),
+                None => return Err(NFDError::UnknownMime { mime: mime.to_owned() }),
+            }
+        }
+        self.filter = Some(Filter::new().group(&mimes.join(", "), &extensions).to_string());
+        self.filter_cstring.take();
+        Ok(self)
+    }
+
+    /// Use the most restrictive filtering the platform offers, so that files outside
+    /// the filter can't be selected at all.
+    ///
+    /// Only Cocoa filters strictly; GTK and Windows always add an "all files"
+    /// entry, so to be sure no other files get through, also check the selection,
+    /// e.g. with `reject`.
+    pub fn strict_extensions(&mut self, strict: bool) -> &mut DialogBuilder<'a> {
+        self.strict_extensions = strict;
+        self
+    }
+
+    /// Prefer, but don't require, files with the given extension.
+    ///
+    /// The extension is offered as the first filter group, selected by default,
+    /// ahead of any groups set with `filter` and the "all files" entry that GTK and
+    /// Windows always add. Cocoa has no filter selector and would hide every other
+    /// file, so there the hint is ignored.
+    pub fn primary_type_hint(&mut self, extension: &str) -> &mut DialogBuilder<'a> {
+        self.type_hint = Some(extension.trim_start_matches('.').to_owned());
+        self.filter_cstring.take();
+        self
+    }
+
+    /// Which filter group is selected when the dialog opens, counting from 0.
+    ///
+    /// GTK and Windows always start on the first group, so with the bundled
+    /// nativefiledialog the chosen group is moved to the front of the list. An
+    /// index past the last group selects the last one. A `primary_type_hint` still
+    /// comes first. Cocoa shows no group selector, so there this is ignored.
+    pub fn default_filter_index(&mut self, index: usize) -> &mut DialogBuilder<'a> {
+        self.default_filter_index = Some(index);
+        self.filter_cstring.take();
+        self
+    }
+
+    /// Like `filter`, but fails straight away if the filter can't be passed to NFD
+    pub fn try_filter(&mut self, filter: &str) -> Result<&mut DialogBuilder<'a>> {
+        CString::new(filter)?;
+        filter::parse(filter)?;
+        self.filter = Some(filter.to_owned());
+        self.filter_cstring.take();
+        Ok(self)
+    }
+
+    /// Like `default_path`, but fails straight away if the path can't be passed to NFD
+    pub fn try_default_path(&mut self, path: &'a str) -> Result<&mut DialogBuilder<'a>> {
+        CString::new(path)?;
+        self.default_path = Some(Cow::Borrowed(path));
+        Ok(self)
+    }
+
+    /// Open in a standard folder such as `KnownFolder::Documents`, looked up when
+    /// the dialog opens. An explicit `default_path` takes precedence; if the folder
+    /// can't be resolved the remembered directory and `default_path_fallbacks` are
+    /// tried as usual, and failing those the platform picks.
+    pub fn default_known_folder(&mut self, folder: KnownFolder) -> &mut DialogBuilder<'a> {
+        self.known_folder = Some(folder);
+        self
+    }
+
+    /// Resolve a relative `default_path`, `default_path_fallbacks` and `suggestions`
+    /// against `dir` instead of the process's current directory, e.g. the
+    /// directory of the open document. Unlike calling `set_current_dir` around the
+    /// dialog, this doesn't touch global state, so it is safe with other threads
+    /// about. The paths the dialog returns are absolute either way; use
+    /// `relative_to` to make them relative.
+    pub fn cwd_context(&mut self, dir: &Path) -> &mut DialogBuilder<'a> {
+        self.cwd_context = Some(dir.to_path_buf());
+        self
+    }
+
+    /// Directories to try in order when no `default_path` is set, e.g. the project
+    /// directory, then home. The first that exists is used as the default path.
+    /// A directory remembered with `remember_as` or `state_token` comes first, so
+    /// the fallbacks only apply until something has been selected; see
+    /// `suggestions` for the full order.
+    pub fn default_path_fallbacks(&mut self, dirs: &[&Path]) -> &mut DialogBuilder<'a> {
+        self.fallbacks = dirs.iter().map(|dir| dir.to_path_buf()).collect();
+        self
+    }
+
+    /// Recent paths to fall back on when no `default_path` is set, most recent first.
+    ///
+    /// When the dialog opens, the default location is chosen in this order:
+    /// the explicit `default_path`, then the `default_known_folder`, then the
+    /// directory remembered for `remember_as`, then the one saved for
+    /// `state_token`, then the first existing directory of
+    /// `default_path_fallbacks`, then the first suggestion that still exists (a
+    /// file suggests its parent directory), then the platform default.
+    pub fn suggestions(&mut self, paths: &[&Path]) -> &mut DialogBuilder<'a> {
+        self.suggestions = paths.iter().map(|p| p.to_path_buf()).collect();
+        self
+    }
+
+    /// Like `remember_as`, but across runs: the directory of each selection is
+    /// saved under `token` in the file given to `set_state_file`, and the next
+    /// dialog with the same token opens there, even after a restart. Each token has
+    /// its own directory, so e.g. `"theme-import"` and `"export"` don't disturb
+    /// each other.
+    ///
+    /// The saved directory ranks just after the one remembered by `remember_as`;
+    /// an explicit `default_path` always takes precedence. Without a state file
+    /// nothing is saved.
+    pub fn state_token(&mut self, token: &str) -> &mut DialogBuilder<'a> {
+        self.state_token = Some(token.to_owned());
+        self
+    }
+
+    /// Remember the directory of each selection under `key` for the rest of the
+    /// process, and open there next time a dialog with the same key has no
+    /// explicit `default_path`. Dialogs share a remembered directory exactly when
+    /// they use the same key; the dialogs of a `DialogSession` keep theirs apart
+    /// from the rest of the process.
+    pub fn remember_as(&mut self, key: &str) -> &mut DialogBuilder<'a> {
+        self.remember_as = Some(key.to_owned());
+        self
+    }
+
+    /// Always open in the default path, even if the OS remembers a more recent folder.
+    ///
+    /// On Windows, `IFileDialog` keeps a per-process memory of the last used folder
+    /// which takes precedence over a folder set with `SetDefaultFolder`; forcing
+    /// uses `SetFolder` so the app's choice wins. The bundled nativefiledialog
+    /// already calls `SetFolder`, and GTK and Cocoa always honour the given folder,
+    /// so for now the default path always wins and leaving this off has no effect.
+    pub fn force_default_path(&mut self, force: bool) -> &mut DialogBuilder<'a> {
+        self.force_default_path = force;
+        self
+    }
+
+    /// Whether the dialog resolves symlinks while navigating; by default the
+    /// platform decides (usually following them). Ignored by the bundled backends.
+    pub fn follow_symlinks(&mut self, follow: bool) -> &mut DialogBuilder<'a> {
+        self.follow_symlinks = Some(follow);
+        self
+    }
+
+    /// Whether the dialog lists hidden files; by default the platform decides.
+    /// Ignored by the bundled backends.
+    pub fn show_hidden(&mut self, show: bool) -> &mut DialogBuilder<'a> {
+        self.show_hidden = Some(show);
+        self
+    }
+
+    /// Whether the dialog shows a preview of the selected file; by default the
+    /// platform decides. Ignored by the bundled backends.
+    pub fn show_preview(&mut self, show: bool) -> &mut DialogBuilder<'a> {
+        self.show_preview = Some(show);
+        self
+    }
+
+    /// Add a shortcut to `path`, shown as `label`, to the dialog's sidebar.
+    /// Ignored by the bundled backends.
+    pub fn add_bookmark(&mut self, path: &Path, label: &str) -> &mut DialogBuilder<'a> {
+        self.bookmarks.push((path.to_path_buf(), label.to_owned()));
+        self
+    }
+
+    /// Files to select when the dialog opens, e.g. the previous selection of a
+    /// multiple file dialog. Ignored by the bundled backends.
+    pub fn preselect(&mut self, paths: &[&Path]) -> &mut DialogBuilder<'a> {
+        self.preselect = paths.iter().map(|path| path.to_path_buf()).collect();
+        self
+    }
+
+    /// Open a multiple file dialog and return the selection as a lazily read `PathSet`,
+    /// or `None` if the user cancelled
+    pub fn open_path_set(&self) -> Result<Option<PathSet>> {
+        let checked = self.warn_empty_directory || self.reject.is_some() || self.validate.is_some();
+        let filtered = checked || self.rust_side_filter;
+        let rewritten = self.relative_to.is_some() || self.separators != SeparatorStyle::Native;
+        if self.timeout.is_some() || self.dry_run || filtered || rewritten {
+            return Ok(match self.open_as(DialogType::MultipleFiles)? {
+                Response::OkayMultiple(paths) | Response::OkayMultipleTimed { paths, .. } => {
+                    Some(PathSet::from_paths(paths))
+                }
+                _ => None,
+            });
+        }
+
+        self.log_ignored_options();
+
+        let default_path = self.effective_default_path();
+        let selection = run_dialog(self.filter_cstring()?, default_path.as_deref(), DialogType::MultipleFiles, self.lossy_replacement)?;
+
+        Ok(match selection {
+            Selection::Multiple(paths) => {
+                if let Some(first) = paths.get(0) {
+                    self.remember_path(&first, DialogType::MultipleFiles);
+                }
+                Some(paths)
+            }
+            _ => None,
+        })
+    }
+
+    /// Open a multiple file dialog to change an earlier selection, with the
+    /// `previous` paths preselected where the backend supports it (see
+    /// `preselect`; the bundled backends don't). `Response::diff_from` tells what
+    /// was added and removed.
+    pub fn reopen_multiple(&self, previous: &[&Path]) -> Result<Response> {
+        let mut builder = self.detach();
+        builder.preselect(previous);
+        builder.open_as(DialogType::MultipleFiles)
+    }
+
+    /// Open a multiple file dialog and call `f` with each selected path, straight from
+    /// the native path set. The `&str` is only valid during the call. Returns the
+    /// number of paths visited, which is 0 if the user cancelled.
+    pub fn open_multiple_for_each<F: FnMut(&str)>(&self, f: F) -> Result<usize> {
+        Ok(match self.open_path_set()? {
+            Some(paths) => {
+                paths.for_each_str(f);
+                paths.len()
+            }
+            None => 0,
+        })
+    }
+
+    /// Like `open_multiple_for_each`, but `on_path` is also given the path's index
+    /// and the total number of paths, `on_path(index, path, total)`, e.g. to drive
+    /// a progress bar during a large import. The total is known before the first
+    /// path is read. The `&str` is only valid during the call. Returns the total,
+    /// which is 0 if the user cancelled.
+    pub fn open_multiple_stream<F: FnMut(usize, &str, usize)>(&self, mut on_path: F) -> Result<usize> {
+        Ok(match self.open_path_set()? {
+            Some(paths) => {
+                let total = paths.len();
+                let mut index = 0;
+                paths.for_each_str(|path| {
+                    on_path(index, path, total);
+                    index += 1;
+                });
+                total
+            }
+            None => 0,
+        })
+    }
+
+    /// Return single selections as `Response::OkayDetailed`, carrying the
+    /// canonicalized path alongside the raw one. Multiple selections are unaffected.
+    pub fn canonicalize(&mut self, canonicalize: bool) -> &mut DialogBuilder<'a> {
+        self.canonicalize = canonicalize;
+        self
+    }
+
+    /// In save dialogs, append the first extension of the filter to a chosen name
+    /// whose extension the filter doesn't accept, e.g. `report` becomes `report.pdf`
+    pub fn force_extension(&mut self, force: bool) -> &mut DialogBuilder<'a> {
+        self.force_extension = force;
+        self
+    }
+
+    /// Which control has focus when the dialog opens; by default the platform
+    /// decides. Ignored by the bundled backends.
+    pub fn focus(&mut self, target: FocusTarget) -> &mut DialogBuilder<'a> {
+        self.focus = Some(target);
+        self
+    }
+
+    /// Give focus back to the parent window once the dialog closes, for window
+    /// managers that otherwise leave it elsewhere. Ignored by the bundled backends,
+    /// which open every dialog without a parent.
+    pub fn restore_focus(&mut self, restore: bool) -> &mut DialogBuilder<'a> {
+        self.restore_focus = restore;
         self
     }
 
-    pub fn default_path(&'a mut self, path: &'a str) -> &mut DialogBuilder {
-        self.default_path = Some(path);
+    /// Return single selections as `Response::OkayWithFilter`.
+    ///
+    /// The bundled backends don't say which filter was active, so `filter_index`
+    /// is inferred as the first group of the `filter` accepting the chosen file's
+    /// extension, and `filter_changed` is always `false`. Both are best effort.
+    pub fn report_filter(&mut self, report: bool) -> &mut DialogBuilder<'a> {
+        self.report_filter = report;
+        self
+    }
+
+    /// Return cancellations as `Response::Cancelled`, with the directory the user
+    /// had browsed to where the backend reports it. Best effort: the bundled
+    /// backends don't, so `last_dir` is currently always `None`.
+    pub fn report_last_dir(&mut self, report: bool) -> &mut DialogBuilder<'a> {
+        self.report_last_dir = report;
+        self
+    }
+
+    /// Offer a read-only checkbox, initially checked if `read_only` is true, and
+    /// return single selections as `Response::OkayReadOnly` with whether it was
+    /// checked when the user confirmed, like Win32's `OFN_READONLY`.
+    ///
+    /// Only the legacy Win32 dialog has this checkbox built in; the common item
+    /// dialog, GTK and Cocoa would all need a custom control, which the bundled
+    /// backends don't add. The checkbox is therefore never shown and `read_only`
+    /// comes back as passed in here, as it does from a Win32 dialog with the
+    /// checkbox hidden. `canonicalize`, `report_filter` and `timed` take precedence.
+    pub fn read_only(&mut self, read_only: bool) -> &mut DialogBuilder<'a> {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// Return selections as `Response::OkayTimed`, or `Response::OkayMultipleTimed`
+    /// for multiple ones, with how long the dialog was open. For single selections
+    /// `canonicalize` and `report_filter` take precedence. `open_path_set` and the
+    /// methods built on it don't report the time.
+    #[cfg(feature = "timing")]
+    pub fn timed(&mut self, timed: bool) -> &mut DialogBuilder<'a> {
+        self.timed = timed;
+        self
+    }
+
+    /// Before showing a save dialog, check that the extension of `default_name`
+    /// is one the filter accepts, failing with `NFDError::ExtensionMismatch` if not.
+    /// Catches mistakes like a `pdf` filter with a default name of `report.txt`.
+    /// Off by default.
+    pub fn strict_save_validation(&mut self, strict: bool) -> &mut DialogBuilder<'a> {
+        self.strict_save_validation = strict;
+        self
+    }
+
+    /// Before showing an open dialog, check that the default directory holds at
+    /// least one file the filter accepts, failing with `NFDError::NoMatchingFiles`
+    /// if it holds none, rather than showing what looks like an empty folder.
+    /// Off by default, and skipped without a default path or filter.
+    ///
+    /// This lists the whole directory before the dialog opens, which can take a
+    /// while for huge or network directories. Only files directly in it count,
+    /// and extensions are compared ignoring case.
+    pub fn warn_empty_directory(&mut self, warn: bool) -> &mut DialogBuilder<'a> {
+        self.warn_empty_directory = warn;
+        self
+    }
+
+    /// Apply the filter in Rust instead of handing it to the native dialog, for
+    /// backends whose filtering is unreliable. Off by default.
+    ///
+    /// **Open dialogs then show all files**, with no filter to choose from, and
+    /// selected files the filter doesn't accept (ignoring case) are dropped
+    /// afterwards, failing with `NFDError::NoMatchingFiles` if none are left. Save
+    /// and folder dialogs keep the native filter.
+    pub fn rust_side_filter(&mut self, rust_side: bool) -> &mut DialogBuilder<'a> {
+        self.rust_side_filter = rust_side;
+        self
+    }
+
+    /// Return selected paths relative to `base`, e.g. a project root.
+    ///
+    /// Paths that aren't under `base` are returned unchanged, or rejected with
+    /// `NFDError::OutsideBase` if `reject_outside_base` is set. Applies to the
+    /// paths in `Response::Okay`, `Response::OkayMultiple`, `Response::OkayWithFilter`,
+    /// `Response::OkayTimed`, `Response::OkayMultipleTimed` and
+    /// `Response::OkayReadOnly`, and to the paths of
+    /// `open_path_set` and the methods built on it, which then read the whole
+    /// selection up front. The raw path of `Response::OkayDetailed` is always left
+    /// as the dialog returned it.
+    pub fn relative_to(&mut self, base: &Path) -> &mut DialogBuilder<'a> {
+        self.relative_to = Some(base.to_path_buf());
+        self
+    }
+
+    /// Fail with `NFDError::OutsideBase` instead of keeping the absolute path when a
+    /// selection isn't under the `relative_to` base
+    pub fn reject_outside_base(&mut self, reject: bool) -> &mut DialogBuilder<'a> {
+        self.reject_outside_base = reject;
+        self
+    }
+
+    /// Rewrite the separators of returned paths, e.g. to `/` for paths stored in a
+    /// portable config file. `Native` by default. Applies to the same paths as
+    /// `relative_to`, after it.
+    ///
+    /// Only Windows paths change, since `\` is an ordinary file name character
+    /// elsewhere. Windows accepts `/` in most paths, but not in `\\?\` paths, so a
+    /// normalized string can stop being valid there; the `canonical` path of
+    /// `Response::OkayDetailed` is never rewritten.
+    pub fn normalize_separators(&mut self, style: SeparatorStyle) -> &mut DialogBuilder<'a> {
+        self.separators = style;
+        self
+    }
+
+    /// Requested size of the dialog window, in pixels. Ignored by the bundled backends.
+    pub fn initial_size(&mut self, width: u32, height: u32) -> &mut DialogBuilder<'a> {
+        self.initial_size = Some((width, height));
+        self
+    }
+
+    /// Drop selected paths for which `predicate` returns true, e.g. the app's own
+    /// temp files, failing with `NFDError::AllRejected` if nothing is left.
+    ///
+    /// Native dialogs can only filter by extension, so this runs after the dialog
+    /// has closed: rejected files are still listed and selectable while browsing.
+    pub fn reject<F: Fn(&Path) -> bool + Send + Sync + 'static>(&mut self, predicate: F) -> &mut DialogBuilder<'a> {
+        self.reject = Some(Arc::new(predicate));
+        self
+    }
+
+    /// By-value form of `reject`
+    pub fn with_reject<F: Fn(&Path) -> bool + Send + Sync + 'static>(mut self, predicate: F) -> DialogBuilder<'a> {
+        self.reject(predicate);
+        self
+    }
+
+    /// Check each selected path with `validator`, failing with
+    /// `NFDError::ValidationFailed` and the reason it returns for the first path it
+    /// refuses, e.g. to insist on files under the project directory.
+    ///
+    /// This runs after the dialog has closed, so a refused selection can't be
+    /// corrected in place; open the dialog again to let the user choose anew.
+    pub fn validate<F>(&mut self, validator: F) -> &mut DialogBuilder<'a>
+        where F: Fn(&Path) -> std::result::Result<(), String> + Send + Sync + 'static
+    {
+        self.validate = Some(Arc::new(validator));
+        self
+    }
+
+    /// By-value form of `validate`
+    pub fn with_validate<F>(mut self, validator: F) -> DialogBuilder<'a>
+        where F: Fn(&Path) -> std::result::Result<(), String> + Send + Sync + 'static
+    {
+        self.validate(validator);
+        self
+    }
+
+    /// Open the dialog maximized. Ignored by the bundled backends.
+    pub fn maximized(&mut self, maximized: bool) -> &mut DialogBuilder<'a> {
+        self.maximized = maximized;
+        self
+    }
+
+    /// How a save dialog treats the chosen file; `Standard` by default.
+    ///
+    /// `Copy` is ignored by the bundled backends.
+    ///
+    /// No native dialog can require an existing file when saving, so
+    /// `ExistingOnly` is checked after the user has picked a path: they can still
+    /// navigate freely and type a new name, which then fails with
+    /// `NFDError::MustExist`.
+    pub fn save_mode(&mut self, mode: SaveMode) -> &mut DialogBuilder<'a> {
+        self.save_mode = mode;
+        self
+    }
+
+    /// Give up waiting for the dialog after `timeout`, failing with `NFDError::Timeout`.
+    /// Applies to every way of opening the dialog.
+    ///
+    /// With a timeout the dialog runs on a background thread; see
+    /// `open_cancellable` for what that means on each platform. On macOS, where
+    /// that isn't possible, every dialog fails with `NFDError::Unsupported`.
+    /// **Timing out does not close the native dialog**, which stays open until the
+    /// user dismisses it.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut DialogBuilder<'a> {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Check the configuration without showing anything: opening runs the same
+    /// validation as usual (filter parsing, `strict_save_validation`, the NUL checks
+    /// on the filter and default path) and then returns `Ok(Response::Cancel)`, or
+    /// `Ok(None)` where a method returns an `Option`. **No dialog is ever shown**,
+    /// so this works in tests and CI without a display.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut DialogBuilder<'a> {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// The character that stands in for bytes of a returned path that aren't valid
+    /// UTF-8, e.g. `'_'` for paths shown to the user or used to build file names.
+    /// U+FFFD, as with `to_string_lossy`, by default.
+    pub fn lossy_replacement(&mut self, replacement: char) -> &mut DialogBuilder<'a> {
+        self.lossy_replacement = replacement;
         self
     }
 
     pub fn open(&self) -> Result<Response> {
-        open_dialog(self.filter, self.default_path, self.dialog_type.clone())
+        self.open_as(self.dialog_type)
+    }
+
+    /// Open a single file dialog and read the whole of the chosen file.
+    /// Returns `None` if the user cancelled.
+    #[cfg(feature = "fs")]
+    pub fn open_and_read(&self) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        match self.open_as(DialogType::SingleFile)?.into_path() {
+            Some(path) => {
+                let contents = std::fs::read(&path)?;
+                Ok(Some((path, contents)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Show a save dialog and write `contents` to the chosen path, honouring
+    /// `force_extension`. Returns the path written, or `None` if the user cancelled.
+    #[cfg(feature = "fs")]
+    pub fn save_and_write(&self, contents: &[u8]) -> Result<Option<PathBuf>> {
+        match self.open_as(DialogType::SaveFile)?.into_path() {
+            Some(path) => {
+                std::fs::write(&path, contents)?;
+                Ok(Some(path))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Show a save dialog and create the chosen path's parent directory if it
+    /// doesn't exist yet, e.g. for a name like `exports/report.pdf` typed into a
+    /// backend that accepts one. Returns the path, or `None` if the user cancelled;
+    /// the file itself isn't created. Unlike `open`, this touches the filesystem.
+    #[cfg(feature = "fs")]
+    pub fn save_ensuring_dir(&self) -> Result<Option<PathBuf>> {
+        let path = match self.open_as(DialogType::SaveFile)?.into_path() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(Some(path))
+    }
+
+    /// Open a single file dialog, then open the chosen file for reading and writing
+    /// with an exclusive advisory lock, so that two instances of an editor don't
+    /// edit the same file. Fails with `NFDError::Locked` if another process holds
+    /// the lock; returns `None` if the user cancelled. The lock is released when the
+    /// `File` is closed.
+    #[cfg(feature = "lock")]
+    pub fn open_exclusive(&self) -> Result<Option<(PathBuf, std::fs::File)>> {
+        use fs2::FileExt;
+
+        let path = match self.open_as(DialogType::SingleFile)?.into_path() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(&path)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some((path, file))),
+            Err(ref err) if err.kind() == fs2::lock_contended_error().kind() => Err(NFDError::Locked { path }),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Open a single file dialog and memory-map the chosen file read-only, for
+    /// large files that shouldn't be read into memory whole. Returns `None` if the
+    /// user cancelled; a file that can't be opened or mapped fails with
+    /// `NFDError::Io`.
+    ///
+    /// # Safety
+    ///
+    /// The map reflects the file as it is on disk, so the file must not be
+    /// truncated or modified, by this or any other process, while the map is
+    /// alive. See `memmap2::Mmap::map`.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap(&self) -> Result<Option<(PathBuf, memmap2::Mmap)>> {
+        let path = match self.open_as(DialogType::SingleFile)?.into_path() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let file = std::fs::File::open(&path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        Ok(Some((path, map)))
+    }
+
+    fn open_as(&self, dialog_type: DialogType) -> Result<Response> {
+        if dialog_type == DialogType::SaveFile && self.strict_save_validation {
+            self.check_default_name()?;
+        }
+        let opens_files = dialog_type == DialogType::SingleFile || dialog_type == DialogType::MultipleFiles;
+        if self.warn_empty_directory && opens_files {
+            self.check_directory_matches()?;
+        }
+
+        if self.dry_run {
+            self.filter_cstring()?;
+            if let Some(default_path) = self.effective_default_path() {
+                CString::new(default_path)?;
+            }
+            return Ok(Response::Cancel);
+        }
+
+        if let Some(timeout) = self.timeout {
+            background::check_supported()?;
+            let mut detached = self.detach();
+            detached.timeout = None;
+            detached.dialog_type = dialog_type;
+            let deadline = Instant::now() + timeout;
+            let receiver = background::spawn(detached);
+            return background::wait(receiver, || Instant::now() >= deadline).unwrap_or(Err(NFDError::Timeout));
+        }
+
+        self.log_ignored_options();
+
+        let default_path = self.effective_default_path();
+        let filter = self.filter_cstring()?;
+        let rust_side_filter = self.rust_side_filter && opens_files;
+        let native_filter = if rust_side_filter { None } else { filter };
+        #[cfg(feature = "timing")]
+        let started = Instant::now();
+        let selection = run_dialog(native_filter, default_path.as_deref(), dialog_type, self.lossy_replacement)?;
+        let mut response = into_response(selection);
+        #[cfg(feature = "timing")]
+        let elapsed = started.elapsed();
+        self.remember(&response, dialog_type);
+
+        if dialog_type == DialogType::SaveFile && self.force_extension {
+            if let (Response::Okay(ref mut path), Some(filter)) = (&mut response, filter.and_then(|f| f.to_str().ok())) {
+                force_extension(path, filter);
+            }
+        }
+
+        if let Some(reject) = self.reject.as_deref() {
+            response = match response {
+                Response::Okay(path) => Response::Okay(keep(vec![path], reject)?.remove(0)),
+                Response::OkayMultiple(paths) => Response::OkayMultiple(keep(paths, reject)?),
+                response => response,
+            };
+        }
+
+        if let (true, Some(filter)) = (rust_side_filter, filter.and_then(|f| f.to_str().ok())) {
+            response = match response {
+                Response::Okay(path) => Response::Okay(accepted_by(vec![path], filter)?.remove(0)),
+                Response::OkayMultiple(paths) => Response::OkayMultiple(accepted_by(paths, filter)?),
+                response => response,
+            };
+        }
+
+        if let Some(validate) = self.validate.as_deref() {
+            for path in response.paths() {
+                validate(Path::new(path)).map_err(|reason| NFDError::ValidationFailed { reason })?;
+            }
+        }
+
+        if dialog_type == DialogType::SaveFile && self.save_mode == SaveMode::ExistingOnly {
+            if let Response::Okay(ref path) = response {
+                if !Path::new(path).is_file() {
+                    return Err(NFDError::MustExist { path: PathBuf::from(path) });
+                }
+            }
+        }
+
+        Ok(match response {
+            Response::Okay(raw) if self.canonicalize => {
+                let canonical = std::fs::canonicalize(&raw).ok();
+                Response::OkayDetailed { raw, canonical }
+            }
+            Response::Okay(path) if self.report_filter => {
+                let path = self.output_path(path)?;
+                let filter_index = self.filter.as_ref().and_then(|filter| matching_group(&path, filter));
+                Response::OkayWithFilter { path, filter_index, filter_changed: false }
+            }
+            #[cfg(feature = "timing")]
+            Response::Okay(path) if self.timed => Response::OkayTimed { path: self.output_path(path)?, elapsed },
+            Response::Okay(path) if self.read_only.is_some() => Response::OkayReadOnly {
+                path: self.output_path(path)?,
+                read_only: self.read_only == Some(true),
+            },
+            Response::Okay(path) => Response::Okay(self.output_path(path)?),
+            #[cfg(feature = "timing")]
+            Response::OkayMultiple(paths) if self.timed => {
+                let paths = paths.into_iter().map(|path| self.output_path(path));
+                Response::OkayMultipleTimed { paths: paths.collect::<Result<_>>()?, elapsed }
+            }
+            Response::OkayMultiple(paths) => {
+                let paths = paths.into_iter().map(|path| self.output_path(path));
+                Response::OkayMultiple(paths.collect::<Result<_>>()?)
+            }
+            Response::Cancel if self.report_last_dir => Response::Cancelled { last_dir: None },
+            response => response,
+        })
+    }
+
+    /// Show a save dialog for exporting in one of several `(label, extension)` types,
+    /// e.g. `export("report", &[("PDF", "pdf"), ("Plain text", "txt")])`.
+    ///
+    /// `filename_stem` is the suggested name *without* an extension; the extension
+    /// comes from the type. Each type becomes a filter group and the chosen path
+    /// gets an extension as with `force_extension`: a name ending in one of the
+    /// types' extensions is kept, anything else gets the first type's extension,
+    /// since the bundled backends don't report which type the user picked. This
+    /// builder's own filter, default name and `force_extension` are overridden.
+    pub fn export(&self, filename_stem: &str, types: &[(&str, &str)]) -> Result<Response> {
+        let filter = types.iter().fold(Filter::new(), |filter, &(label, extension)| {
+            filter.group(label, &[extension.trim_start_matches('.')])
+        });
+
+        let mut builder = self.detach();
+        builder.filter(&filter.to_string()).default_name(filename_stem).force_extension(true);
+        builder.open_as(DialogType::SaveFile)
+    }
+
+    /// Let the user pick an existing file or name a new one, e.g. to open a project
+    /// or start one. Returns `None` if the user cancelled.
+    ///
+    /// None of the bundled backends let an open dialog return a file that doesn't
+    /// exist, so this shows a save dialog and checks afterwards whether the chosen
+    /// file exists. The save dialogs of every platform ask the user to confirm
+    /// replacing an existing file; confirming here only means that it is opened.
+    pub fn open_or_new(&self) -> Result<Option<OpenOrNew>> {
+        Ok(self.open_as(DialogType::SaveFile)?.into_path().map(|path| {
+            if path.is_file() {
+                OpenOrNew::Existing(path)
+            } else {
+                OpenOrNew::New(path)
+            }
+        }))
+    }
+
+    /// Open the dialog, returning the selected path or `None` if the user cancelled.
+    /// For multiple selections only the first path is returned.
+    pub fn open_path(&self) -> Result<Option<PathBuf>> {
+        self.open().map(Response::into_path)
+    }
+
+    /// Open the dialog and return `map(path)` for the selected path, or `on_cancel`
+    /// if the user cancelled, e.g. `dialog().open_or(Config::default(), Config::load)`.
+    /// For multiple selections only the first path is mapped.
+    pub fn open_or<T, F: Fn(String) -> T>(&self, on_cancel: T, map: F) -> Result<T> {
+        Ok(self.open()?.into_first().map_or(on_cancel, map))
+    }
+
+    /// Open the dialog and return `f(path)` for the selected path, or `None` if the
+    /// user cancelled, e.g. `dialog().open_map(Config::load)`. For multiple
+    /// selections only the first path is mapped.
+    pub fn open_map<T, F: FnOnce(PathBuf) -> T>(&self, f: F) -> Result<Option<T>> {
+        Ok(self.open_path()?.map(f))
+    }
+
+    /// Open a multiple file dialog and return `f(path)` for each selected path, in
+    /// order. Empty if the user cancelled.
+    pub fn open_multiple_map<T, F: FnMut(PathBuf) -> T>(&self, f: F) -> Result<Vec<T>> {
+        Ok(match self.open_as(DialogType::MultipleFiles)?.outcome() {
+            Outcome::Selected(paths) => paths.into_iter().map(f).collect(),
+            Outcome::Cancelled => Vec::new(),
+        })
+    }
+
+    /// Like `open_path`, but maps `NFDError` into the caller's own error type
+    pub fn open_mapped<E, F: Fn(NFDError) -> E>(&self, f: F) -> std::result::Result<Option<PathBuf>, E> {
+        self.open_path().map_err(f)
+    }
+
+    /// Open the dialog on a background thread, giving up once `flag` is set.
+    ///
+    /// **The native dialog can't be closed from Rust.** Setting `flag` only makes
+    /// this call return `NFDError::Cancelled` so the caller can carry on; the dialog
+    /// stays on screen until the user dismisses it, its result is discarded, and
+    /// until then further dialogs fail with `NFDError::DialogAlreadyOpen`.
+    ///
+    /// The dialog runs off the calling thread. Cocoa doesn't allow that, so on
+    /// macOS this fails with `NFDError::Unsupported`; GTK only tolerates it if no
+    /// other thread uses GTK.
+    pub fn open_cancellable(&self, flag: Arc<AtomicBool>) -> Result<Response> {
+        background::check_supported()?;
+        let receiver = background::spawn(self.detach());
+        background::wait(receiver, || flag.load(Ordering::SeqCst)).unwrap_or(Err(NFDError::Cancelled))
+    }
+
+    /// Open the dialog on a background thread and send the result to `sender` once
+    /// the user is done, for event loops that can't block, e.g. an egui app that
+    /// polls the receiver with `try_recv` every frame. Returns immediately.
+    ///
+    /// Nothing is sent if the dialog thread panics, which the receiver sees as a
+    /// disconnect. Until the result arrives further dialogs fail with
+    /// `NFDError::DialogAlreadyOpen`.
+    ///
+    /// Running off the main thread depends on the platform:
+    ///
+    /// * **macOS**: Cocoa panels must run on the main thread, so no dialog is shown
+    ///   and `NFDError::Unsupported` is sent straight away.
+    /// * **Windows**: works; the dialog thread initializes COM itself, but the
+    ///   dialog has no owner window, so it isn't modal to the app's window.
+    /// * **Linux and other unix**: works only if no other thread uses GTK, as GTK is
+    ///   not thread safe.
+    pub fn open_to_channel(&self, sender: Sender<Result<Response>>) {
+        match background::check_supported() {
+            Ok(()) => background::spawn_to(self.detach(), sender),
+            Err(err) => {
+                let _ = sender.send(Err(err));
+            }
+        }
+    }
+
+    /// A copy of this builder that owns all its settings, for handing to another thread
+    fn detach(&self) -> DialogBuilder<'static> {
+        DialogBuilder {
+            filter: self.filter.clone(),
+            filter_cstring: self.filter_cstring.clone(),
+            default_path: self.default_path.as_ref().map(|path| Cow::Owned(path.to_string())),
+            default_name: self.default_name.clone(),
+            type_hint: self.type_hint.clone(),
+            default_filter_index: self.default_filter_index,
+            suggestions: self.suggestions.clone(),
+            fallbacks: self.fallbacks.clone(),
+            known_folder: self.known_folder,
+            cwd_context: self.cwd_context.clone(),
+            remember_as: self.remember_as.clone(),
+            memory: self.memory.clone(),
+            state_token: self.state_token.clone(),
+            force_default_path: self.force_default_path,
+            canonicalize: self.canonicalize,
+            force_extension: self.force_extension,
+            relative_to: self.relative_to.clone(),
+            reject_outside_base: self.reject_outside_base,
+            separators: self.separators,
+            reject: self.reject.clone(),
+            validate: self.validate.clone(),
+            initial_size: self.initial_size,
+            maximized: self.maximized,
+            follow_symlinks: self.follow_symlinks,
+            show_hidden: self.show_hidden,
+            show_preview: self.show_preview,
+            bookmarks: self.bookmarks.clone(),
+            preselect: self.preselect.clone(),
+            save_mode: self.save_mode,
+            focus: self.focus,
+            restore_focus: self.restore_focus,
+            report_filter: self.report_filter,
+            report_last_dir: self.report_last_dir,
+            read_only: self.read_only,
+            #[cfg(feature = "timing")]
+            timed: self.timed,
+            strict_save_validation: self.strict_save_validation,
+            strict_extensions: self.strict_extensions,
+            warn_empty_directory: self.warn_empty_directory,
+            rust_side_filter: self.rust_side_filter,
+            timeout: self.timeout,
+            dry_run: self.dry_run,
+            lossy_replacement: self.lossy_replacement,
+            dialog_type: self.dialog_type,
+        }
+    }
+
+    fn log_ignored_options(&self) {
+        if cfg!(windows) && !self.force_default_path && self.default_path.is_some() {
+            debug!("nfd: the bundled backend always forces default_path");
+        }
+        if cfg!(target_os = "macos") && self.type_hint.is_some() {
+            ignored("primary_type_hint");
+        }
+        if self.default_name.is_some() {
+            ignored("default_name");
+        }
+        if cfg!(target_os = "macos") && self.default_filter_index.is_some() {
+            ignored("default_filter_index");
+        }
+        if !cfg!(target_os = "macos") && self.strict_extensions {
+            ignored("strict_extensions");
+        }
+        if self.initial_size.is_some() {
+            ignored("initial_size");
+        }
+        if self.maximized {
+            ignored("maximized");
+        }
+        if self.follow_symlinks.is_some() {
+            ignored("follow_symlinks");
+        }
+        if self.show_hidden.is_some() {
+            ignored("show_hidden");
+        }
+        if self.show_preview.is_some() {
+            ignored("show_preview");
+        }
+        if !self.bookmarks.is_empty() {
+            ignored("add_bookmark");
+        }
+        if !self.preselect.is_empty() {
+            ignored("preselect");
+        }
+        if self.save_mode == SaveMode::Copy {
+            ignored("save_mode");
+        }
+        if self.focus.is_some() {
+            ignored("focus");
+        }
+        if self.restore_focus {
+            ignored("restore_focus");
+        }
+        if self.read_only.is_some() {
+            ignored("read_only");
+        }
+    }
+
+    fn check_default_name(&self) -> Result<()> {
+        let name_ext = match self.default_name.as_ref().and_then(|name| Path::new(name).extension()) {
+            Some(name_ext) => name_ext.to_string_lossy(),
+            None => return Ok(()),
+        };
+        let filter = match self.effective_filter() {
+            Some(filter) => filter,
+            None => return Ok(()),
+        };
+
+        let allowed: Vec<String> = filter::parse(&filter)?.into_iter().flatten().map(str::to_owned).collect();
+        if allowed.is_empty() || allowed.iter().any(|ext| ext.eq_ignore_ascii_case(&name_ext)) {
+            Ok(())
+        } else {
+            Err(NFDError::ExtensionMismatch { name_ext: name_ext.into_owned(), allowed })
+        }
+    }
+
+    fn check_directory_matches(&self) -> Result<()> {
+        let dir = match self.effective_default_path().and_then(|path| paths::resolve_default(&path).dir) {
+            Some(dir) => PathBuf::from(dir),
+            None => return Ok(()),
+        };
+        let filter = match self.effective_filter() {
+            Some(filter) => filter,
+            None => return Ok(()),
+        };
+        let allowed: Vec<&str> = filter::parse(&filter)?.into_iter().flatten().collect();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Let the dialog deal with a directory it can't read
+            Err(_) => return Ok(()),
+        };
+
+        let matches = entries.filter_map(|entry| entry.ok()).any(|entry| {
+            let path = entry.path();
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            path.is_file() && extension.is_some_and(|ext| allowed.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+        });
+        if allowed.is_empty() || matches {
+            Ok(())
+        } else {
+            Err(NFDError::NoMatchingFiles { dir })
+        }
+    }
+
+    fn remember(&self, response: &Response, dialog_type: DialogType) {
+        let path = match *response {
+            Response::Okay(ref path) => path,
+            Response::OkayMultiple(ref paths) if !paths.is_empty() => &paths[0],
+            _ => return,
+        };
+        self.remember_path(path, dialog_type);
+    }
+
+    /// Remember the directory of `path` under `remember_as` and `state_token`
+    fn remember_path(&self, path: &str, dialog_type: DialogType) {
+        if let Some(ref key) = self.remember_as {
+            self.memory().remember_selection(key, path, dialog_type);
+        }
+        if let (Some(token), Some(dir)) = (self.state_token.as_ref(), memory::selection_dir(path, dialog_type)) {
+            state::store(token, dir);
+        }
+    }
+
+    /// The session's directory memory, or the process-wide one
+    fn memory(&self) -> &DirectoryMemory {
+        match self.memory {
+            Some(ref memory) => memory,
+            None => memory::global(),
+        }
+    }
+
+    /// A selected path as returned to the caller, after `relative_to` and
+    /// `normalize_separators`
+    fn output_path(&self, path: String) -> Result<String> {
+        let path = self.relative_path(path)?;
+        if cfg!(windows) && self.separators == SeparatorStyle::ForwardSlash {
+            Ok(path.replace('\\', "/"))
+        } else {
+            Ok(path)
+        }
+    }
+
+    fn relative_path(&self, path: String) -> Result<String> {
+        let base = match self.relative_to {
+            Some(ref base) => base,
+            None => return Ok(path),
+        };
+        match Path::new(&path).strip_prefix(base) {
+            Ok(relative) => Ok(relative.to_string_lossy().into_owned()),
+            Err(_) if self.reject_outside_base => Err(NFDError::OutsideBase {
+                path: PathBuf::from(path),
+                base: base.clone(),
+            }),
+            Err(_) => Ok(path),
+        }
+    }
+
+    /// The effective filter as passed to NFD, converted on first use only. The
+    /// default path isn't cached the same way since it depends on what exists on
+    /// disk, and on remembered directories, at the time the dialog opens.
+    fn filter_cstring(&self) -> Result<Option<&CStr>> {
+        if self.filter_cstring.get().is_none() {
+            let converted = match self.effective_filter() {
+                Some(filter) => Some(filter_cstring(&filter)?),
+                None => None,
+            };
+            let _ = self.filter_cstring.set(converted);
+        }
+        Ok(self.filter_cstring.get().and_then(Option::as_deref))
+    }
+
+    fn effective_filter(&self) -> Option<String> {
+        let filter = match (self.filter.as_ref(), self.default_filter_index) {
+            (Some(filter), Some(index)) if !cfg!(target_os = "macos") => Some(select_group(filter, index)),
+            _ => self.filter.clone(),
+        };
+
+        let hint = match self.type_hint {
+            Some(ref hint) if !cfg!(target_os = "macos") => hint,
+            _ => return filter,
+        };
+        let name = format!("{} files", hint.to_uppercase());
+        let hinted = Filter::new().group(&name, &[hint]).to_string();
+
+        Some(match filter {
+            Some(ref filter) if !filter.is_empty() => format!("{};{}", hinted, filter),
+            _ => hinted,
+        })
+    }
+
+    fn effective_default_path(&self) -> Option<String> {
+        if let Some(ref path) = self.default_path {
+            return match self.cwd_context {
+                Some(_) => self.in_context(Path::new(path.as_ref())).to_str().map(str::to_owned),
+                None => Some(path.to_string()),
+            };
+        }
+        if let Some(path) = self.known_folder.and_then(KnownFolder::path) {
+            return path.to_str().map(str::to_owned);
+        }
+
+        let remembered = self.remember_as.as_ref().and_then(|key| self.memory().get(key));
+        let saved = || self.state_token.as_ref().and_then(|token| state::load(token)).filter(|dir| dir.is_dir());
+        let fallback = || self.fallbacks.iter().map(|dir| self.in_context(dir)).find(|dir| dir.is_dir());
+        remembered.or_else(saved).or_else(fallback).or_else(|| self.suggested_path()).and_then(|p| p.to_str().map(str::to_owned))
+    }
+
+    /// `path` resolved against `cwd_context` if it is relative
+    fn in_context(&self, path: &Path) -> PathBuf {
+        match self.cwd_context {
+            Some(ref base) if path.is_relative() => base.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    fn suggested_path(&self) -> Option<PathBuf> {
+        self.suggestions.iter().map(|path| self.in_context(path)).filter_map(|path| {
+            if path.is_dir() {
+                Some(path)
+            } else if path.is_file() {
+                path.parent().map(Path::to_path_buf)
+            } else {
+                None
+            }
+        }).next()
     }
 }
 
+/// Define `with_x(self, ..) -> DialogBuilder` for each setter `x(&mut self, ..)`, so
+/// a builder can be configured by value, e.g. to return it from a function
+macro_rules! by_value_setters {
+    ($($(#[$attr:meta])* $with:ident => $setter:ident($($arg:ident: $ty:ty),*);)*) => {
+        impl<'a> DialogBuilder<'a> {
+            $(
+                #[doc = concat!("By-value form of `", stringify!($setter), "`")]
+                $(#[$attr])*
+                pub fn $with(mut self, $($arg: $ty),*) -> DialogBuilder<'a> {
+                    self.$setter($($arg),*);
+                    self
+                }
+            )*
+        }
+    };
+}
+
+by_value_setters! {
+    with_filter => filter(filter: &str);
+    with_default_path => default_path(path: &'a str);
+    with_default_name => default_name(name: &str);
+    with_default_name_from => default_name_from(path: &Path);
+    with_filter_parts => filter_parts(groups: &[(&str, &[&str])]);
+    with_primary_type_hint => primary_type_hint(extension: &str);
+    with_default_filter_index => default_filter_index(index: usize);
+    with_strict_extensions => strict_extensions(strict: bool);
+    with_default_path_fallbacks => default_path_fallbacks(dirs: &[&Path]);
+    with_default_known_folder => default_known_folder(folder: KnownFolder);
+    with_cwd_context => cwd_context(dir: &Path);
+    with_suggestions => suggestions(paths: &[&Path]);
+    with_remember_as => remember_as(key: &str);
+    with_state_token => state_token(token: &str);
+    with_force_default_path => force_default_path(force: bool);
+    with_follow_symlinks => follow_symlinks(follow: bool);
+    with_show_hidden => show_hidden(show: bool);
+    with_show_preview => show_preview(show: bool);
+    with_bookmark => add_bookmark(path: &Path, label: &str);
+    with_preselect => preselect(paths: &[&Path]);
+    with_canonicalize => canonicalize(canonicalize: bool);
+    with_force_extension => force_extension(force: bool);
+    with_focus => focus(target: FocusTarget);
+    with_restore_focus => restore_focus(restore: bool);
+    with_report_filter => report_filter(report: bool);
+    with_report_last_dir => report_last_dir(report: bool);
+    with_read_only => read_only(read_only: bool);
+    #[cfg(feature = "timing")]
+    with_timed => timed(timed: bool);
+    with_strict_save_validation => strict_save_validation(strict: bool);
+    with_warn_empty_directory => warn_empty_directory(warn: bool);
+    with_rust_side_filter => rust_side_filter(rust_side: bool);
+    with_relative_to => relative_to(base: &Path);
+    with_reject_outside_base => reject_outside_base(reject: bool);
+    with_normalize_separators => normalize_separators(style: SeparatorStyle);
+    with_initial_size => initial_size(width: u32, height: u32);
+    with_maximized => maximized(maximized: bool);
+    with_save_mode => save_mode(mode: SaveMode);
+    with_timeout => timeout(timeout: Duration);
+    with_dry_run => dry_run(dry_run: bool);
+    with_lossy_replacement => lossy_replacement(replacement: char);
+}
+
+/// Start a `DialogSession`, whose dialogs remember their directory among themselves
+pub fn session() -> DialogSession {
+    DialogSession::new()
+}
+
 pub fn dialog<'a>() -> DialogBuilder<'a> {
     DialogBuilder::new(DialogType::SingleFile)
 }
@@ -89,8 +1483,138 @@ pub fn dialog_save<'a>() -> DialogBuilder<'a> {
     DialogBuilder::new(DialogType::SaveFile)
 }
 
+/// Builder for a dialog that picks a single folder
+pub fn dialog_pick_folder<'a>() -> DialogBuilder<'a> {
+    DialogBuilder::new(DialogType::PickFolder)
+}
+
+/// Builder for a dialog that picks several folders, see `DialogType::PickFolderMultiple`
+pub fn dialog_pick_folder_multiple<'a>() -> DialogBuilder<'a> {
+    DialogBuilder::new(DialogType::PickFolderMultiple)
+}
+
 pub type Result<T> = std::result::Result<T, NFDError>;
 
+/// Check a filter list the way opening a dialog would, without opening one.
+///
+/// Malformed lists fail with `NFDError::InvalidFilter`, and lists containing a
+/// NUL byte with `NFDError::NulError`.
+pub fn validate_filter(filter_list: &str) -> Result<()> {
+    filter_cstring(filter_list).map(|_| ())
+}
+
+/// Release of nativefiledialog the bundled backend is built from
+const BACKEND_VERSION: (u32, u32) = (1, 1);
+
+/// The `(major, minor)` version of the nativefiledialog library linked in.
+///
+/// NFD has no runtime version query; the library is compiled into this crate, so
+/// this is the release it is built from.
+pub fn backend_version() -> (u32, u32) {
+    BACKEND_VERSION
+}
+
+/// Fail with `NFDError::VersionTooOld` unless the linked nativefiledialog is at
+/// least `major.minor`, so an app can check once at startup.
+///
+/// Everything in this crate works with the bundled 1.1, except for
+/// `DialogType::PickFolderMultiple`, which no release of nativefiledialog offers.
+pub fn require_version(major: u32, minor: u32) -> Result<()> {
+    if backend_version() >= (major, minor) {
+        Ok(())
+    } else {
+        Err(NFDError::VersionTooOld { found: backend_version(), required: (major, minor) })
+    }
+}
+
+/// Set the application ID the GTK file chooser is attributed to, e.g.
+/// `org.example.Editor`, which compositors use for the window icon and grouping.
+///
+/// Call it once, before the first dialog: GTK reads the ID when it initialises,
+/// which nfd does on first use. Does nothing on Windows and macOS.
+pub fn set_app_id(app_id: &str) -> Result<()> {
+    let app_id = CString::new(app_id)?;
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    unsafe {
+        // glib copies the string
+        g_set_prgname(app_id.as_ptr());
+    }
+    #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+    let _ = app_id;
+    Ok(())
+}
+
+fn ignored(option: &str) {
+    debug!("nfd: {} is not supported by the bundled backend on this platform, ignoring", option);
+}
+
+/// The paths `reject` doesn't match, failing if that leaves none
+fn keep(paths: Vec<String>, reject: &PathPredicate) -> Result<Vec<String>> {
+    let kept: Vec<String> = paths.into_iter().filter(|path| !reject(Path::new(path))).collect();
+    if kept.is_empty() {
+        Err(NFDError::AllRejected)
+    } else {
+        Ok(kept)
+    }
+}
+
+/// The paths `filter` accepts, failing if that leaves none
+fn accepted_by(paths: Vec<String>, filter: &str) -> Result<Vec<String>> {
+    if filter::parse(filter)?.is_empty() {
+        return Ok(paths);
+    }
+    let dir = paths.first().and_then(|path| Path::new(path).parent()).map(Path::to_path_buf);
+    let accepted: Vec<String> = paths.into_iter().filter(|path| matching_group(path, filter).is_some()).collect();
+    if accepted.is_empty() {
+        Err(NFDError::NoMatchingFiles { dir: dir.unwrap_or_default() })
+    } else {
+        Ok(accepted)
+    }
+}
+
+/// Index of the first group in `filter` that accepts the extension of `path`
+fn matching_group(path: &str, filter: &str) -> Option<usize> {
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str())?;
+    let groups = filter::parse(filter).ok()?;
+    groups.iter().position(|group| group.iter().any(|allowed| allowed.eq_ignore_ascii_case(extension)))
+}
+
+/// Append the filter's first extension to `path` unless it already has one the filter accepts
+fn force_extension(path: &mut String, filter: &str) {
+    let groups = match filter::parse(filter) {
+        Ok(groups) => groups,
+        Err(_) => return,
+    };
+    let first = match groups.first().and_then(|group| group.first()) {
+        Some(first) => *first,
+        None => return,
+    };
+
+    let accepted = Path::new(path.as_str()).extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+        groups.iter().flatten().any(|allowed| allowed.eq_ignore_ascii_case(ext))
+    });
+    if !accepted {
+        path.push('.');
+        path.push_str(first);
+    }
+}
+
+/// Move the filter group at `index` to the front of an NFD filter list
+fn select_group(filter: &str, index: usize) -> String {
+    let mut groups: Vec<&str> = filter.split(';').collect();
+    let last = groups.len() - 1;
+    let index = if index > last {
+        debug!("nfd: default_filter_index {} is out of range, using the last group", index);
+        last
+    } else {
+        index
+    };
+
+    let group = groups.remove(index);
+    groups.insert(0, group);
+    groups.join(";")
+}
+
 /// Open single file dialog
 pub fn open_file_dialog(filter_list: Option<&str>, default_path: Option<&str>) -> Result<Response> {
     open_dialog(filter_list, default_path, DialogType::SingleFile)
@@ -106,32 +1630,127 @@ pub fn open_save_dialog(filter_list: Option<&str>, default_path: Option<&str>) -
     open_dialog(filter_list, default_path, DialogType::SaveFile)
 }
 
-/// Open save dialog
+/// Open folder picker dialog, returning a single folder
 pub fn open_pick_folder(default_path: Option<&str>) -> Result<Response> {
     open_dialog(None, default_path, DialogType::PickFolder)
 }
 
-pub fn open_dialog(filter_list: Option<&str>, default_path: Option<&str>, dialog_type: DialogType) -> Result<Response> {
-    let result;
-    let filter_list_cstring;
-    let default_path_cstring;
+/// Open folder picker dialog that can return several folders.
+/// See `DialogType::PickFolderMultiple` for platform support.
+pub fn open_pick_folder_multiple(default_path: Option<&str>) -> Result<Response> {
+    open_dialog(None, default_path, DialogType::PickFolderMultiple)
+}
 
-    let filter_list_ptr = match filter_list {
-        Some(fl_str) => {
-            filter_list_cstring = CString::new(fl_str)?;
-            filter_list_cstring.as_ptr()
-        }
-        None => std::ptr::null()
+/// Open a dialog of the given type.
+///
+/// If `default_path` doesn't exist, the dialog opens in its nearest existing
+/// parent directory instead, so stale paths to deleted files or folders still
+/// land somewhere sensible.
+///
+/// Only one dialog can be open at a time; see `NFDError::DialogAlreadyOpen` and
+/// the `serialize_calls` feature.
+///
+/// Inside a Flatpak or Snap sandbox (see `is_sandboxed`) this fails with
+/// `NFDError::Unsupported` rather than showing a dialog that can't see the host's
+/// files.
+pub fn open_dialog(filter_list: Option<&str>, default_path: Option<&str>, dialog_type: DialogType) -> Result<Response> {
+    let filter_list_cstring = match filter_list {
+        Some(fl_str) => Some(filter_cstring(fl_str)?),
+        None => None
     };
 
-    let default_path_ptr = match default_path {
-        Some(dp_str) => {
-            default_path_cstring = CString::new(dp_str)?;
-            default_path_cstring.as_ptr()
-        }
-        None => std::ptr::null()
+    run_dialog(filter_list_cstring.as_deref(), default_path, dialog_type, char::REPLACEMENT_CHARACTER).map(into_response)
+}
+
+/// Open a dialog with a filter list and default path already converted for NFD,
+/// skipping the conversion and checks `open_dialog` makes on every call.
+///
+/// `default_path` is handed to the backend as is, without falling back to the
+/// nearest existing parent. Only one dialog can be open at a time, as with
+/// `open_dialog`.
+///
+/// # Safety
+///
+/// `filter_list` must be a valid filter list, i.e. `validate_filter` must accept
+/// it. The native backends copy extensions into fixed size buffers without bounds
+/// checks, so an overlong extension corrupts memory.
+pub unsafe fn open_raw(filter_list: Option<&CStr>, default_path: Option<&CStr>, dialog_type: DialogType) -> Result<Response> {
+    let _guard = DialogGuard::acquire()?;
+    run_recorded(filter_list, default_path, dialog_type, char::REPLACEMENT_CHARACTER).map(into_response)
+}
+
+fn into_response(selection: Selection) -> Response {
+    match selection {
+        Selection::Single(path) => Response::Okay(path),
+        Selection::Multiple(paths) => Response::OkayMultiple(paths.iter().collect()),
+        Selection::Cancel => Response::Cancel,
+    }
+}
+
+/// Check a filter list and convert it for NFD
+fn filter_cstring(filter_list: &str) -> Result<CString> {
+    filter::parse(filter_list)?;
+    Ok(CString::new(filter_list)?)
+}
+
+/// What the native dialog handed back, before it is turned into a `Response`
+enum Selection {
+    Single(String),
+    Multiple(PathSet),
+    Cancel,
+}
+
+fn run_dialog(filter_list: Option<&CStr>, default_path: Option<&str>, dialog_type: DialogType, replacement: char) -> Result<Selection> {
+    if let Some(dp_str) = default_path {
+        CString::new(dp_str)?;
+    }
+    let default_location = default_path.map(paths::resolve_default);
+    if let Some(name) = default_location.as_ref().and_then(|location| location.name.as_ref()) {
+        debug!("nfd: the bundled backend can't suggest the file name `{}`, ignoring", name);
+    }
+
+    let default_path_cstring = match default_location.and_then(|location| location.dir) {
+        Some(dp_str) => Some(CString::new(dp_str)?),
+        None => None
     };
 
+    let _guard = DialogGuard::acquire()?;
+
+    run_recorded(filter_list, default_path_cstring.as_deref(), dialog_type, replacement)
+}
+
+/// Run the backend, keeping the details of a failure for `last_diagnostics`
+fn run_recorded(filter_list: Option<&CStr>, default_path: Option<&CStr>, dialog_type: DialogType, replacement: char) -> Result<Selection> {
+    backend::mark_dialog_shown();
+    let result = run_backend(filter_list, default_path, dialog_type, replacement);
+    if let Err(NFDError::Backend { ref message, .. }) = result {
+        diagnostics::record(filter_list, default_path, dialog_type, message);
+    }
+    result
+}
+
+#[cfg_attr(feature = "integration-sim", allow(dead_code))]
+/// Show the dialog. Returned paths that aren't valid UTF-8 get `replacement` in
+/// place of the invalid bytes.
+fn run_native(filter_list: Option<&CStr>, default_path: Option<&CStr>, dialog_type: DialogType, replacement: char) -> Result<Selection> {
+    if cfg!(not(any(unix, windows))) {
+        return Err(NFDError::Unsupported("no native file dialogs on this target".to_owned()));
+    }
+    if dialog_type == DialogType::PickFolderMultiple {
+        return Err(NFDError::Unsupported("picking multiple folders".to_owned()));
+    }
+    if is_sandboxed() {
+        // GTK dialogs opened from inside the sandbox can't reach the host's files
+        return Err(NFDError::Unsupported(
+            "file dialogs inside a Flatpak or Snap sandbox, which need the xdg-desktop-portal \
+             backend that this build doesn't include".to_owned()));
+    }
+
+    let result;
+
+    let filter_list_ptr = filter_list.map_or(std::ptr::null(), CStr::as_ptr);
+    let default_path_ptr = default_path.map_or(std::ptr::null(), CStr::as_ptr);
+
     let mut out_path: *mut c_char = std::ptr::null_mut();
     let ptr_out_path = &mut out_path as *mut *mut c_char;
 
@@ -152,32 +1771,93 @@ pub fn open_dialog(filter_list: Option<&str>, default_path: Option<&str>, dialog
                 NFD_SaveDialog(filter_list_ptr, default_path_ptr, ptr_out_path)
             },
 
-            DialogType::PickFolder => {
+            DialogType::PickFolder | DialogType::PickFolderMultiple => {
                 NFD_PickFolder(default_path_ptr, ptr_out_path)
             },
         };
 
         match result {
             nfdresult_t::NFD_OKAY =>{
-                if dialog_type == DialogType::MultipleFiles {
-                    let count = NFD_PathSet_GetCount(&out_multiple);
-                    let mut res = Vec::with_capacity(count);
-                    for i in 0..count {
-                        let path = CStr::from_ptr(NFD_PathSet_GetPath(&out_multiple, i)).to_string_lossy().into_owned();
-                        res.push(path)
-
-                    }
-
-                    NFD_PathSet_Free(ptr_out_multiple);
-
-                    Ok(Response::OkayMultiple(res))
+                if dialog_type.is_multiple() {
+                    Ok(Selection::Multiple(PathSet::from_raw(out_multiple, replacement)))
                 } else {
-                    Ok(Response::Okay(CStr::from_ptr(out_path).to_string_lossy().into_owned()))
+                    Ok(Selection::Single(match utf8::decode_lossy(CStr::from_ptr(out_path).to_bytes(), replacement) {
+                        Cow::Borrowed(path) => pool::string_from(path),
+                        Cow::Owned(path) => path,
+                    }))
                 }
             },
 
-            nfdresult_t::NFD_CANCEL => Ok(Response::Cancel),
-            nfdresult_t::NFD_ERROR => Err(NFDError::Error(CStr::from_ptr(NFD_GetError()).to_string_lossy().into_owned())),
+            nfdresult_t::NFD_CANCEL => Ok(Selection::Cancel),
+            nfdresult_t::NFD_ERROR => Err(NFDError::Backend {
+                code: nfdresult_t::NFD_ERROR as i32,
+                // NFD_GetError only keeps a message, never the HRESULT or NSError behind it
+                platform_code: None,
+                message: CStr::from_ptr(NFD_GetError()).to_string_lossy().into_owned(),
+            }),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh directory under the system temp dir, unique to this test and process
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nfd-lib-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn ext() {
+        let mut builder = dialog();
+        builder.ext(".png").unwrap();
+        assert_eq!(builder.effective_filter().as_deref(), Some("png"));
+        for &extension in &["", ".", "..", "p ng"] {
+            assert!(matches!(dialog().ext(extension), Err(NFDError::InvalidFilter { .. })), "{:?}", extension);
+        }
+    }
+
+    #[test]
+    fn first_existing_fallback() {
+        let dir = temp_dir("fallbacks");
+        let (missing, project, home) = (dir.join("missing"), dir.join("project"), dir.join("home"));
+        fs::create_dir(&project).unwrap();
+        fs::create_dir(&home).unwrap();
+
+        let mut builder = dialog();
+        builder.default_path_fallbacks(&[&missing, &project, &home]);
+        assert_eq!(builder.effective_default_path().as_deref(), project.to_str());
+
+        builder.default_path_fallbacks(&[&missing, &home]);
+        assert_eq!(builder.effective_default_path().as_deref(), home.to_str());
+
+        builder.default_path_fallbacks(&[&missing]);
+        assert_eq!(builder.effective_default_path(), None);
+
+        builder.default_path_fallbacks(&[&project]).default_path("/explicit");
+        assert_eq!(builder.effective_default_path().as_deref(), Some("/explicit"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remembered_before_fallbacks() {
+        let dir = temp_dir("remembered");
+        let (project, last_used) = (dir.join("project"), dir.join("last-used"));
+        fs::create_dir(&project).unwrap();
+        fs::create_dir(&last_used).unwrap();
+
+        let mut builder = dialog();
+        builder.memory = Some(Arc::new(DirectoryMemory::new()));
+        builder.remember_as("k").default_path_fallbacks(&[&project]);
+        assert_eq!(builder.effective_default_path().as_deref(), project.to_str());
+
+        builder.remember_path(last_used.join("a.txt").to_str().unwrap(), DialogType::SingleFile);
+        assert_eq!(builder.effective_default_path().as_deref(), last_used.to_str());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use DialogType;
+
+/// Last used directories, keyed by a caller-chosen name such as "import"
+pub struct DirectoryMemory {
+    dirs: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl DirectoryMemory {
+    pub fn new() -> DirectoryMemory {
+        DirectoryMemory { dirs: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn get(&self, key: &str) -> Option<PathBuf> {
+        self.dirs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(key).cloned()
+    }
+
+    pub fn set(&self, key: &str, dir: PathBuf) {
+        self.dirs.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(key.to_owned(), dir);
+    }
+
+    /// Remember the directory a selection was made in: the folder itself for
+    /// folder pickers, the containing directory otherwise
+    pub fn remember_selection(&self, key: &str, path: &str, dialog_type: DialogType) {
+        if let Some(dir) = selection_dir(path, dialog_type) {
+            self.set(key, dir.to_path_buf());
+        }
+    }
+}
+
+/// The directory a selection was made in: the folder itself for folder pickers,
+/// the containing directory otherwise
+pub fn selection_dir(path: &str, dialog_type: DialogType) -> Option<&Path> {
+    let path = Path::new(path);
+    match dialog_type {
+        DialogType::PickFolder | DialogType::PickFolderMultiple => Some(path),
+        _ => path.parent(),
+    }
+}
+
+/// The process-wide memory used by `DialogBuilder::remember_as`
+pub fn global() -> &'static DirectoryMemory {
+    static MEMORY: OnceLock<DirectoryMemory> = OnceLock::new();
+    MEMORY.get_or_init(DirectoryMemory::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_selections_store_their_parent() {
+        let memory = DirectoryMemory::new();
+        memory.remember_selection("import", "/data/in/a.csv", DialogType::SingleFile);
+        assert_eq!(memory.get("import"), Some(PathBuf::from("/data/in")));
+        memory.remember_selection("import", "/data/out/b.csv", DialogType::SaveFile);
+        assert_eq!(memory.get("import"), Some(PathBuf::from("/data/out")));
+    }
+
+    #[test]
+    fn folder_selections_are_stored_as_is() {
+        let memory = DirectoryMemory::new();
+        memory.remember_selection("export", "/data/out", DialogType::PickFolder);
+        assert_eq!(memory.get("export"), Some(PathBuf::from("/data/out")));
+    }
+
+    #[test]
+    fn keys_are_separate() {
+        let memory = DirectoryMemory::new();
+        memory.set("a", PathBuf::from("/a"));
+        memory.set("b", PathBuf::from("/b"));
+        assert_eq!(memory.get("a"), Some(PathBuf::from("/a")));
+        assert_eq!(memory.get("b"), Some(PathBuf::from("/b")));
+        assert_eq!(memory.get("c"), None);
+    }
+
+    #[test]
+    fn selection_without_parent_is_ignored() {
+        let memory = DirectoryMemory::new();
+        memory.remember_selection("root", "/", DialogType::SingleFile);
+        assert_eq!(memory.get("root"), None);
+    }
+}
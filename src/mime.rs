@@ -0,0 +1,106 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+/// MIME types and the extensions they map to, most common extension first
+const MIME_TYPES: &[(&str, &[&str])] = &[
+    ("application/json", &["json"]),
+    ("application/pdf", &["pdf"]),
+    ("application/xml", &["xml"]),
+    ("application/zip", &["zip"]),
+    ("application/gzip", &["gz"]),
+    ("application/x-tar", &["tar"]),
+    ("application/rtf", &["rtf"]),
+    ("application/msword", &["doc"]),
+    ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", &["docx"]),
+    ("application/vnd.ms-excel", &["xls"]),
+    ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", &["xlsx"]),
+    ("application/vnd.oasis.opendocument.text", &["odt"]),
+    ("audio/flac", &["flac"]),
+    ("audio/mpeg", &["mp3"]),
+    ("audio/ogg", &["ogg", "oga"]),
+    ("audio/wav", &["wav"]),
+    ("font/otf", &["otf"]),
+    ("font/ttf", &["ttf"]),
+    ("font/woff", &["woff"]),
+    ("font/woff2", &["woff2"]),
+    ("image/bmp", &["bmp"]),
+    ("image/gif", &["gif"]),
+    ("image/jpeg", &["jpg", "jpeg"]),
+    ("image/png", &["png"]),
+    ("image/svg+xml", &["svg"]),
+    ("image/tiff", &["tif", "tiff"]),
+    ("image/webp", &["webp"]),
+    ("text/css", &["css"]),
+    ("text/csv", &["csv"]),
+    ("text/html", &["html", "htm"]),
+    ("text/markdown", &["md", "markdown"]),
+    ("text/plain", &["txt"]),
+    ("video/mp4", &["mp4"]),
+    ("video/mpeg", &["mpeg", "mpg"]),
+    ("video/webm", &["webm"]),
+];
+
+/// The extensions for a MIME type, or `None` if it isn't in the table
+pub fn extensions(mime: &str) -> Option<&'static [&'static str]> {
+    MIME_TYPES.iter()
+        .find(|&&(known, _)| known.eq_ignore_ascii_case(mime))
+        .map(|&(_, extensions)| extensions)
+}
+
+/// The MIME type for a file extension, ignoring case, or `None` if it isn't in the table
+pub fn mime_type(extension: &str) -> Option<&'static str> {
+    MIME_TYPES.iter()
+        .find(|&&(_, extensions)| extensions.iter().any(|known| known.eq_ignore_ascii_case(extension)))
+        .map(|&(mime, _)| mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_types() {
+        assert_eq!(mime_type("png"), Some("image/png"));
+        assert_eq!(mime_type("jpg"), Some("image/jpeg"));
+        assert_eq!(mime_type("jpeg"), Some("image/jpeg"));
+        assert_eq!(mime_type("pdf"), Some("application/pdf"));
+        assert_eq!(mime_type("txt"), Some("text/plain"));
+        assert_eq!(mime_type("mp4"), Some("video/mp4"));
+        assert_eq!(mime_type("JSON"), Some("application/json"));
+    }
+
+    #[test]
+    fn unknown_types() {
+        assert_eq!(mime_type("xyz"), None);
+        assert_eq!(mime_type(""), None);
+        assert_eq!(extensions("application/x-unknown"), None);
+    }
+
+    #[test]
+    fn inverse_of_extensions() {
+        for &(mime, _) in MIME_TYPES {
+            for extension in extensions(mime).unwrap() {
+                assert_eq!(mime_type(extension), Some(mime));
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::path::Path;
+
+/// Where a dialog should really open for the requested `default_path`.
+pub struct DefaultLocation {
+    /// The nearest directory that exists, if any
+    pub dir: Option<String>,
+    /// The file name the caller seemed to want, if it pointed at a file
+    pub name: Option<String>,
+}
+
+/// Resolve a requested default path against the filesystem.
+///
+/// Existing directories are used as-is. Otherwise the path is walked up to its
+/// nearest existing ancestor, so a since-deleted file or folder still opens
+/// close to where it was. The last component is kept as a file name when the
+/// path is an existing file, or when it no longer exists but has an extension.
+pub fn resolve_default(path: &str) -> DefaultLocation {
+    let path = Path::new(path);
+    if path.is_dir() {
+        return DefaultLocation { dir: path.to_str().map(str::to_owned), name: None };
+    }
+
+    let name = if path.is_file() || path.extension().is_some() {
+        path.file_name().and_then(|name| name.to_str()).map(str::to_owned)
+    } else {
+        None
+    };
+    let dir = path.ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.is_dir())
+        .and_then(|ancestor| ancestor.to_str())
+        .map(str::to_owned);
+
+    DefaultLocation { dir, name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A fresh directory under the system temp dir, unique to this test and process
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nfd-paths-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn resolve(path: &Path) -> DefaultLocation {
+        resolve_default(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn existing_directory() {
+        let dir = temp_dir("existing");
+        let location = resolve(&dir);
+        assert_eq!(location.dir.as_deref(), dir.to_str());
+        assert_eq!(location.name, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn existing_file() {
+        let dir = temp_dir("file");
+        let file = dir.join("notes");
+        fs::write(&file, "").unwrap();
+        let location = resolve(&file);
+        assert_eq!(location.dir.as_deref(), dir.to_str());
+        assert_eq!(location.name.as_deref(), Some("notes"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deleted_file() {
+        let dir = temp_dir("deleted-file");
+        let file = dir.join("report.pdf");
+        fs::write(&file, "").unwrap();
+        fs::remove_file(&file).unwrap();
+        let location = resolve(&file);
+        assert_eq!(location.dir.as_deref(), dir.to_str());
+        assert_eq!(location.name.as_deref(), Some("report.pdf"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deleted_directory() {
+        let dir = temp_dir("deleted-dir");
+        let nested = dir.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::remove_dir_all(dir.join("a")).unwrap();
+        let location = resolve(&nested);
+        assert_eq!(location.dir.as_deref(), dir.to_str());
+        assert_eq!(location.name, None);
+
+        // A file in a deleted directory still keeps its name
+        let location = resolve(&nested.join("photo.png"));
+        assert_eq!(location.dir.as_deref(), dir.to_str());
+        assert_eq!(location.name.as_deref(), Some("photo.png"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
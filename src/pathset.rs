@@ -0,0 +1,222 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::path::PathBuf;
+
+use ffi::*;
+use pool;
+use utf8;
+
+/// The paths picked in a multiple selection, read lazily from NFD's native path set.
+///
+/// Nothing is copied out of the native set until a path is asked for, and the set
+/// is freed when the `PathSet` is dropped.
+pub struct PathSet {
+    inner: Inner,
+    /// Stands in for invalid UTF-8 in native paths
+    replacement: char,
+}
+
+enum Inner {
+    Native(nfdpathset_t),
+    Owned(Vec<String>),
+}
+
+impl PathSet {
+    /// Take ownership of a path set filled in by `NFD_OpenDialogMultiple`, reading
+    /// paths that aren't valid UTF-8 with `replacement` for the invalid bytes
+    pub(crate) unsafe fn from_raw(raw: nfdpathset_t, replacement: char) -> PathSet {
+        PathSet { inner: Inner::Native(raw), replacement }
+    }
+
+    /// A path set that isn't backed by NFD, for simulated and mocked dialogs
+    pub(crate) fn from_paths(paths: Vec<String>) -> PathSet {
+        PathSet { inner: Inner::Owned(paths), replacement: char::REPLACEMENT_CHARACTER }
+    }
+
+    pub fn len(&self) -> usize {
+        match self.inner {
+            Inner::Native(ref raw) => unsafe { NFD_PathSet_GetCount(raw) },
+            Inner::Owned(ref paths) => paths.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The path at `index`, or `None` if out of range
+    pub fn get(&self, index: usize) -> Option<String> {
+        self.get_lossy(index).map(|path| match path {
+            Cow::Borrowed(path) => pool::string_from(path),
+            Cow::Owned(path) => path,
+        })
+    }
+
+    /// The path, if exactly one was selected. Only that one path is converted.
+    pub fn single(&self) -> Option<String> {
+        if self.len() == 1 {
+            self.get(0)
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the paths, converting each one as it is reached
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { set: self, index: 0 }
+    }
+
+    /// Call `f` with each path in turn. The `&str` borrows from the native set (unless
+    /// it had to be repaired from invalid UTF-8) and is only valid during the call.
+    pub fn for_each_str<F: FnMut(&str)>(&self, mut f: F) {
+        for index in 0..self.len() {
+            if let Some(path) = self.get_lossy(index) {
+                f(&path);
+            }
+        }
+    }
+
+    fn get_lossy(&self, index: usize) -> Option<Cow<'_, str>> {
+        if index >= self.len() {
+            return None;
+        }
+        match self.inner {
+            Inner::Native(ref raw) => unsafe {
+                let path = NFD_PathSet_GetPath(raw, index);
+                if path.is_null() {
+                    None
+                } else {
+                    Some(utf8::decode_lossy(CStr::from_ptr(path).to_bytes(), self.replacement))
+                }
+            },
+            Inner::Owned(ref paths) => Some(Cow::Borrowed(&paths[index])),
+        }
+    }
+}
+
+impl Drop for PathSet {
+    fn drop(&mut self) {
+        if let Inner::Native(ref mut raw) = self.inner {
+            unsafe { NFD_PathSet_Free(raw) }
+        }
+    }
+}
+
+/// Iterator over the paths of a `PathSet`
+pub struct Iter<'a> {
+    set: &'a PathSet,
+    index: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let path = self.set.get(self.index);
+        if path.is_some() {
+            self.index += 1;
+        }
+        path
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl IntoIterator for PathSet {
+    type Item = PathBuf;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { set: Some(self), index: 0 }
+    }
+}
+
+/// Owning iterator over the paths of a `PathSet`, freeing the native set as soon
+/// as the last path has been read
+pub struct IntoIter {
+    set: Option<PathSet>,
+    index: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        let path = self.set.as_ref().and_then(|set| set.get(self.index));
+        match path {
+            Some(path) => {
+                self.index += 1;
+                Some(PathBuf::from(path))
+            }
+            None => {
+                self.set = None;
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.as_ref().map_or(0, |set| set.len().saturating_sub(self.index));
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_set(paths: &[&str]) -> PathSet {
+        PathSet::from_paths(paths.iter().map(|path| path.to_string()).collect())
+    }
+
+    #[test]
+    fn single() {
+        assert_eq!(path_set(&[]).single(), None);
+        assert_eq!(path_set(&["/a"]).single(), Some("/a".to_owned()));
+        assert_eq!(path_set(&["/a", "/b"]).single(), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let paths = path_set(&["/srv/a.txt", "/srv/b.txt"]);
+        let mut iter = paths.into_iter();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        assert_eq!(iter.next(), Some(PathBuf::from("/srv/a.txt")));
+        assert_eq!(iter.size_hint(), (1, Some(1)));
+        assert_eq!(iter.next(), Some(PathBuf::from("/srv/b.txt")));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+
+        let mut collected = Vec::new();
+        for path in path_set(&["/x", "/y"]) {
+            collected.push(path);
+        }
+        assert_eq!(collected, vec![PathBuf::from("/x"), PathBuf::from("/y")]);
+        assert_eq!(path_set(&[]).into_iter().next(), None);
+    }
+}
@@ -0,0 +1,73 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! Reuse of the `String`s that hold returned paths, with the `pool` feature.
+//!
+//! Buffers handed back through `Response::recycle` are kept per thread and reused
+//! for the paths of later dialogs on the same thread. Without the feature every
+//! path is a fresh allocation.
+
+#[cfg(feature = "pool")]
+use std::cell::RefCell;
+
+/// Most buffers kept per thread; extra ones are freed
+#[cfg(feature = "pool")]
+const MAX_POOLED: usize = 64;
+
+#[cfg(feature = "pool")]
+thread_local! {
+    static BUFFERS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A `String` holding `path`, in a pooled buffer if one is big enough
+#[cfg(feature = "pool")]
+pub fn string_from(path: &str) -> String {
+    let buffer = BUFFERS.with(|buffers| {
+        let mut buffers = buffers.borrow_mut();
+        let index = buffers.iter().position(|buffer| buffer.capacity() >= path.len());
+        index.map(|index| buffers.swap_remove(index))
+    });
+    match buffer {
+        Some(mut buffer) => {
+            buffer.push_str(path);
+            buffer
+        }
+        None => path.to_owned(),
+    }
+}
+
+#[cfg(not(feature = "pool"))]
+pub fn string_from(path: &str) -> String {
+    path.to_owned()
+}
+
+/// Return a buffer to this thread's pool
+#[cfg(feature = "pool")]
+pub fn give(mut buffer: String) {
+    buffer.clear();
+    BUFFERS.with(|buffers| {
+        let mut buffers = buffers.borrow_mut();
+        if buffers.len() < MAX_POOLED {
+            buffers.push(buffer);
+        }
+    });
+}
@@ -0,0 +1,30 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! The items most programs need, for `use nfd::prelude::*;`
+//!
+//! `nfd::Result` is left out so that it doesn't shadow `std::result::Result`.
+
+pub use {dialog, dialog_multiple, dialog_save, dialog_pick_folder, dialog_pick_folder_multiple};
+pub use {open_dialog, open_file_dialog, open_file_multiple_dialog, open_save_dialog, open_pick_folder};
+pub use {DialogBuilder, DialogConfig, DialogType, FileDialog, Filter, KnownFolder, NFDError, Outcome, PathSet};
+pub use {OpenOrNew, Response, SaveMode, SeparatorStyle};
@@ -0,0 +1,355 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use escape::{escape, unescape};
+use {DialogConfig, DialogType, FileDialog, NFDError, Response, Result};
+
+const HEADER: &str = "# nfd replay 1";
+
+/// Wraps another `FileDialog`, such as `NativeDialog`, and writes every dialog it
+/// opens and what came back to a file that `ReplayDialog` can play back.
+///
+/// Only single, multiple and cancelled selections and error messages are kept;
+/// other `Response` variants are recorded as a single selection of their path.
+/// A failure to write the file is logged and doesn't affect the dialog.
+///
+/// # File format
+///
+/// A UTF-8 text file with one dialog per line, after a `# nfd replay 1` header:
+///
+/// ```text
+/// <type>\t<filter>\t<default path>\t<result>[\t<field>...]
+/// ```
+///
+/// * `type` is `single`, `multiple`, `save`, `folder` or `folders`.
+/// * `filter` and `default path` are `-` when unset, otherwise `=` followed by
+///   the value.
+/// * `result` is `okay` followed by the path, `multiple` followed by one field
+///   per path, `cancel`, or `error` followed by the error message.
+///
+/// Within fields `\`, tab, newline and carriage return are written as `\\`, `\t`,
+/// `\n` and `\r`. Lines starting with `#` are ignored, so recordings can be
+/// annotated by hand.
+pub struct RecordingDialog<D> {
+    inner: D,
+    file: Mutex<File>,
+}
+
+impl<D: FileDialog> RecordingDialog<D> {
+    /// Record into `path`, replacing anything already there
+    pub fn create(inner: D, path: &Path) -> io::Result<RecordingDialog<D>> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", HEADER)?;
+        Ok(RecordingDialog { inner, file: Mutex::new(file) })
+    }
+}
+
+impl<D: FileDialog> FileDialog for RecordingDialog<D> {
+    fn open(&self, cfg: &DialogConfig) -> Result<Response> {
+        let result = self.inner.open(cfg);
+        let line = format_call(cfg, &result);
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = writeln!(file, "{}", line) {
+            warn!("nfd: couldn't record dialog: {}", err);
+        }
+        result
+    }
+}
+
+/// How `ReplayDialog` picks the recorded dialog to answer a call with
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ReplayMatch {
+    /// The next recorded dialog, whatever it was opened with
+    Index,
+    /// The first recorded dialog not yet replayed whose type, filter and default
+    /// path are those of the call, so calls may come in a different order
+    Config,
+}
+
+/// A fake dialog answering with the results saved by a `RecordingDialog`, see
+/// there for the file format.
+///
+/// A call with nothing left to match it fails with `NFDError::Error`, rather than
+/// being cancelled as with `MockDialog`, since it means the code under test no
+/// longer opens the dialogs that were recorded.
+pub struct ReplayDialog {
+    calls: Mutex<Vec<Option<(DialogConfig, Recorded)>>>,
+    matching: ReplayMatch,
+}
+
+impl ReplayDialog {
+    /// Load a recording, failing with `NFDError::Error` if a line is malformed
+    pub fn open(path: &Path, matching: ReplayMatch) -> Result<ReplayDialog> {
+        let contents = fs::read_to_string(path)?;
+        let mut calls = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_call(line) {
+                Some(call) => calls.push(Some(call)),
+                None => {
+                    return Err(NFDError::Error(format!(
+                        "{}:{}: malformed replay line", path.display(), number + 1)));
+                }
+            }
+        }
+        Ok(ReplayDialog { calls: Mutex::new(calls), matching })
+    }
+}
+
+impl FileDialog for ReplayDialog {
+    fn open(&self, cfg: &DialogConfig) -> Result<Response> {
+        let mut calls = self.calls.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let matching = self.matching;
+        let found = calls.iter_mut().find(|call| match **call {
+            Some((ref recorded, _)) => matching == ReplayMatch::Index || recorded == cfg,
+            None => false,
+        });
+        match found.and_then(Option::take) {
+            Some((_, recorded)) => recorded.into_result(),
+            None => Err(NFDError::Error(format!("no recorded dialog left for {:?}", cfg))),
+        }
+    }
+}
+
+/// A recorded result
+enum Recorded {
+    Okay(String),
+    Multiple(Vec<String>),
+    Cancel,
+    Error(String),
+}
+
+impl Recorded {
+    fn into_result(self) -> Result<Response> {
+        match self {
+            Recorded::Okay(path) => Ok(Response::Okay(path)),
+            Recorded::Multiple(paths) => Ok(Response::OkayMultiple(paths)),
+            Recorded::Cancel => Ok(Response::Cancel),
+            Recorded::Error(message) => Err(NFDError::Error(message)),
+        }
+    }
+}
+
+fn format_call(cfg: &DialogConfig, result: &Result<Response>) -> String {
+    let mut fields = vec![
+        type_name(cfg.dialog_type).to_owned(),
+        format_optional(cfg.filter.as_deref()),
+        format_optional(cfg.default_path.as_deref()),
+    ];
+    match *result {
+        Ok(Response::OkayMultiple(ref paths)) | Ok(Response::OkayMultipleTimed { ref paths, .. }) => {
+            fields.push("multiple".to_owned());
+            fields.extend(paths.iter().map(|path| escape(path)));
+        }
+        Ok(ref response) => match response.paths().first() {
+            Some(path) => {
+                fields.push("okay".to_owned());
+                fields.push(escape(path));
+            }
+            None => fields.push("cancel".to_owned()),
+        },
+        Err(ref err) => {
+            fields.push("error".to_owned());
+            fields.push(escape(&err.to_string()));
+        }
+    }
+    fields.join("\t")
+}
+
+fn parse_call(line: &str) -> Option<(DialogConfig, Recorded)> {
+    let mut fields = line.split('\t');
+    let cfg = DialogConfig {
+        dialog_type: parse_type(fields.next()?)?,
+        filter: parse_optional(fields.next()?)?,
+        default_path: parse_optional(fields.next()?)?,
+    };
+    let recorded = match fields.next()? {
+        "okay" => Recorded::Okay(unescape(fields.next()?)?),
+        "multiple" => Recorded::Multiple(fields.by_ref().map(unescape).collect::<Option<_>>()?),
+        "cancel" => Recorded::Cancel,
+        "error" => Recorded::Error(unescape(fields.next()?)?),
+        _ => return None,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((cfg, recorded))
+}
+
+fn type_name(dialog_type: DialogType) -> &'static str {
+    match dialog_type {
+        DialogType::SingleFile => "single",
+        DialogType::MultipleFiles => "multiple",
+        DialogType::SaveFile => "save",
+        DialogType::PickFolder => "folder",
+        DialogType::PickFolderMultiple => "folders",
+    }
+}
+
+fn parse_type(name: &str) -> Option<DialogType> {
+    Some(match name {
+        "single" => DialogType::SingleFile,
+        "multiple" => DialogType::MultipleFiles,
+        "save" => DialogType::SaveFile,
+        "folder" => DialogType::PickFolder,
+        "folders" => DialogType::PickFolderMultiple,
+        _ => return None,
+    })
+}
+
+fn format_optional(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("={}", escape(value)),
+        None => "-".to_owned(),
+    }
+}
+
+/// `None` if malformed, `Some(None)` for an unset value
+fn parse_optional(field: &str) -> Option<Option<String>> {
+    if field == "-" {
+        Some(None)
+    } else if let Some(value) = field.strip_prefix('=') {
+        unescape(value).map(Some)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use MockDialog;
+
+    /// A fresh directory under the system temp dir, unique to this test and process
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nfd-replay-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(dialog_type: DialogType, filter: Option<&str>, default_path: Option<&str>) -> DialogConfig {
+        DialogConfig {
+            filter: filter.map(str::to_owned),
+            default_path: default_path.map(str::to_owned),
+            dialog_type,
+        }
+    }
+
+    /// The dialogs `record` opens, in order
+    fn configs() -> Vec<DialogConfig> {
+        vec![
+            config(DialogType::SingleFile, Some("png,jpg;pdf"), Some("/tmp/in\tout")),
+            config(DialogType::MultipleFiles, None, Some("C:\\Users")),
+            config(DialogType::SaveFile, None, None),
+            config(DialogType::PickFolder, Some("-"), Some("=")),
+        ]
+    }
+
+    /// Record `configs` into `path`, answered with a path containing a newline, a
+    /// multiple selection, a cancellation and an error
+    fn record(path: &Path) {
+        let mock = MockDialog::new();
+        mock.respond(Ok(Response::Okay("/tmp/new\nline.png".to_owned())))
+            .respond(Ok(Response::OkayMultiple(vec!["C:\\a".to_owned(), "b\t c".to_owned()])))
+            .respond(Ok(Response::Cancel))
+            .respond(Err(NFDError::Error("no\\display".to_owned())));
+        let recording = RecordingDialog::create(mock, path).unwrap();
+        for cfg in configs() {
+            let _ = recording.open(&cfg);
+        }
+    }
+
+    fn assert_replayed(result: Result<Response>, index: usize) {
+        match (index, result) {
+            (0, Ok(Response::Okay(ref path))) => assert_eq!(path, "/tmp/new\nline.png"),
+            (1, Ok(Response::OkayMultiple(ref paths))) => assert_eq!(paths, &["C:\\a", "b\t c"]),
+            (2, Ok(Response::Cancel)) => (),
+            (3, Err(NFDError::Error(ref message))) => assert_eq!(message, "no\\display"),
+            (index, Ok(response)) => panic!("dialog {} replayed as {:?}", index, response.paths()),
+            (index, Err(err)) => panic!("dialog {} replayed as {:?}", index, err),
+        }
+    }
+
+    #[test]
+    fn round_trip_by_index() {
+        let dir = temp_dir("index");
+        let path = dir.join("dialogs.replay");
+        record(&path);
+
+        let replay = ReplayDialog::open(&path, ReplayMatch::Index).unwrap();
+        let other = config(DialogType::PickFolderMultiple, None, None);
+        assert_replayed(replay.open(&other), 0);
+        for index in 1..4 {
+            assert_replayed(replay.open(&configs()[index]), index);
+        }
+        assert!(matches!(replay.open(&other), Err(NFDError::Error(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn round_trip_by_config() {
+        let dir = temp_dir("config");
+        let path = dir.join("dialogs.replay");
+        record(&path);
+
+        let replay = ReplayDialog::open(&path, ReplayMatch::Config).unwrap();
+        for (index, cfg) in configs().iter().enumerate().rev() {
+            assert_replayed(replay.open(cfg), index);
+        }
+        assert!(matches!(replay.open(&configs()[0]), Err(NFDError::Error(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let dir = temp_dir("malformed");
+        let path = dir.join("dialogs.replay");
+        let lines = [
+            "single\t-\t-",
+            "single\t-\t-\tokay",
+            "single\t-\t-\tokay\t/a\t/b",
+            "single\t-\t-\tokay\t/a\\x",
+            "single\tpng\t-\tcancel",
+            "single\t-\t-\tmaybe",
+            "picker\t-\t-\tcancel",
+        ];
+        for line in &lines {
+            fs::write(&path, format!("{}\n# a comment\n\n{}\n", HEADER, line)).unwrap();
+            match ReplayDialog::open(&path, ReplayMatch::Index) {
+                Err(NFDError::Error(message)) => {
+                    assert_eq!(message, format!("{}:4: malformed replay line", path.display()));
+                }
+                other => panic!("{:?} loaded as {:?}", line, other.map(|_| ())),
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
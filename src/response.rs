@@ -0,0 +1,434 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "pool")]
+use pool;
+use mime;
+use uri;
+
+/// Result of opening a file dialog
+pub enum Response {
+    /// User pressed okay. `String` is the file path selected
+    Okay(String),
+    /// User pressed okay on mupliple selections. Result contains a Vec of all the files
+    OkayMultiple(Vec<String>),
+    /// User pressed okay, with `canonicalize` enabled. `raw` is the path exactly as
+    /// returned by the dialog, `canonical` is `None` if it couldn't be canonicalized
+    OkayDetailed { raw: String, canonical: Option<PathBuf> },
+    /// User pressed okay, with `report_filter` enabled. `filter_index` is the filter
+    /// group the file was chosen with, and `filter_changed` whether the user switched
+    /// groups; both are best effort, see `DialogBuilder::report_filter`
+    OkayWithFilter { path: String, filter_index: Option<usize>, filter_changed: bool },
+    /// User pressed okay, with `timed` enabled. `elapsed` is how long the dialog
+    /// was open. Only returned with the `timing` feature, but always present so
+    /// that enabling it doesn't break exhaustive matches elsewhere in the build
+    OkayTimed { path: String, elapsed: Duration },
+    /// User pressed okay on multiple selections, with `timed` enabled, see `OkayTimed`
+    OkayMultipleTimed { paths: Vec<String>, elapsed: Duration },
+    /// User pressed okay, with `read_only` set. `read_only` is whether the user
+    /// left the read-only checkbox checked, see `DialogBuilder::read_only`
+    OkayReadOnly { path: String, read_only: bool },
+    /// User pressed cancel
+    Cancel,
+    /// User pressed cancel, with `report_last_dir` enabled. `last_dir` is the
+    /// directory the user had browsed to, where the backend reports it; the
+    /// bundled backends never do, so it is currently always `None`
+    Cancelled { last_dir: Option<PathBuf> },
+}
+
+/// A `Response` reduced to whether anything was selected, see `Response::outcome`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// One or more paths were selected
+    Selected(Vec<PathBuf>),
+    Cancelled,
+}
+
+impl Response {
+    /// Collapse single and multiple selections into one list of paths
+    pub fn outcome(self) -> Outcome {
+        match self {
+            Response::Okay(path) => Outcome::Selected(vec![PathBuf::from(path)]),
+            Response::OkayMultiple(paths) | Response::OkayMultipleTimed { paths, .. } => {
+                Outcome::Selected(paths.into_iter().map(PathBuf::from).collect())
+            }
+            Response::OkayDetailed { raw, .. } => Outcome::Selected(vec![PathBuf::from(raw)]),
+            Response::OkayWithFilter { path, .. } => Outcome::Selected(vec![PathBuf::from(path)]),
+            Response::OkayTimed { path, .. } => Outcome::Selected(vec![PathBuf::from(path)]),
+            Response::OkayReadOnly { path, .. } => Outcome::Selected(vec![PathBuf::from(path)]),
+            Response::Cancel | Response::Cancelled { .. } => Outcome::Cancelled,
+        }
+    }
+
+    /// The selected path, or the first of several, as a `PathBuf`. `None` on cancel.
+    pub fn into_path(self) -> Option<PathBuf> {
+        self.into_first().map(PathBuf::from)
+    }
+
+    /// The selected path, or the first of several, as returned by the dialog
+    pub(crate) fn into_first(self) -> Option<String> {
+        match self {
+            Response::Okay(path) => Some(path),
+            Response::OkayMultiple(paths) | Response::OkayMultipleTimed { paths, .. } => paths.into_iter().next(),
+            Response::OkayDetailed { raw, .. } => Some(raw),
+            Response::OkayWithFilter { path, .. } => Some(path),
+            Response::OkayTimed { path, .. } => Some(path),
+            Response::OkayReadOnly { path, .. } => Some(path),
+            Response::Cancel | Response::Cancelled { .. } => None,
+        }
+    }
+
+    /// Total size in bytes of the selected files. 0 on cancel.
+    pub fn total_size(&self) -> io::Result<u64> {
+        let mut total = 0;
+        for path in self.paths() {
+            total += fs::metadata(path)?.len();
+        }
+        Ok(total)
+    }
+
+    /// Whether the selected path, or the first of several, is a symbolic link
+    /// itself rather than what it points to. `false` on cancel.
+    pub fn is_symlink(&self) -> io::Result<bool> {
+        match self.paths().first() {
+            Some(path) => Ok(fs::symlink_metadata(path)?.file_type().is_symlink()),
+            None => Ok(false),
+        }
+    }
+
+    /// Whether each selected path is a symbolic link, in order, as with `is_symlink`
+    pub fn symlink_flags(&self) -> io::Result<Vec<bool>> {
+        self.paths().into_iter().map(|path| Ok(fs::symlink_metadata(path)?.file_type().is_symlink())).collect()
+    }
+
+    /// The selected path, or the first of several, as a `file://` URI. `None` on
+    /// cancel, or if the path is relative, e.g. because of `relative_to`.
+    pub fn as_file_uri(&self) -> Option<String> {
+        self.paths().first().and_then(|path| uri::file_uri(path))
+    }
+
+    /// Every selected path as a `file://` URI, skipping relative paths
+    pub fn as_file_uris(&self) -> Vec<String> {
+        self.paths().into_iter().filter_map(uri::file_uri).collect()
+    }
+
+    /// The MIME type of the selected file, or the first of several, going by its
+    /// extension. `None` on cancel or for extensions the built-in table doesn't
+    /// know, which only covers common document, image, audio and video types.
+    pub fn mime_type(&self) -> Option<String> {
+        self.paths().first().and_then(|path| path_mime_type(path))
+    }
+
+    /// The MIME type of every selected path, in order, as with `mime_type`
+    pub fn mime_types(&self) -> Vec<Option<String>> {
+        self.paths().into_iter().map(path_mime_type).collect()
+    }
+
+    /// Every selected path paired with its MIME type, as with `mime_type`, e.g. to
+    /// show a type icon next to each file of an import
+    pub fn typed_paths(&self) -> Vec<(PathBuf, Option<String>)> {
+        self.paths().into_iter().map(|path| (PathBuf::from(path), path_mime_type(path))).collect()
+    }
+
+    /// Every selected path paired with its sidecar, the file of the same name with
+    /// `extension`, e.g. `photo.xmp` for `photo.jpg` with `"xmp"`, if it exists
+    pub fn with_sidecar(&self, extension: &str) -> Vec<(PathBuf, Option<PathBuf>)> {
+        let extension = extension.trim_start_matches('.');
+        self.paths().into_iter().map(|path| {
+            let path = PathBuf::from(path);
+            let sidecar = path.with_extension(extension);
+            let sidecar = if sidecar != path && sidecar.is_file() { Some(sidecar) } else { None };
+            (path, sidecar)
+        }).collect()
+    }
+
+    /// The paths added to and removed from `previous` by this selection, as
+    /// `(added, removed)` in the order of the selection and of `previous`. A
+    /// cancelled dialog changes nothing, so both are empty.
+    pub fn diff_from(&self, previous: &[&Path]) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        if let Response::Cancel | Response::Cancelled { .. } = *self {
+            return (Vec::new(), Vec::new());
+        }
+
+        let selected: Vec<&Path> = self.paths().into_iter().map(Path::new).collect();
+        let added = selected.iter().filter(|path| !previous.contains(path)).map(|path| path.to_path_buf()).collect();
+        let removed = previous.iter().filter(|path| !selected.contains(path)).map(|path| path.to_path_buf()).collect();
+        (added, removed)
+    }
+
+    /// The selected paths bucketed by lowercased extension. Paths without an
+    /// extension go under `""`.
+    pub fn grouped_by_extension(&self) -> BTreeMap<String, Vec<PathBuf>> {
+        let mut groups = BTreeMap::new();
+        for path in self.paths() {
+            let extension = Path::new(path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            groups.entry(extension).or_insert_with(Vec::new).push(PathBuf::from(path));
+        }
+        groups
+    }
+
+    /// The deepest directory containing every selected path, e.g. `/photos` for
+    /// `/photos/a.jpg` and `/photos/b.jpg`. `None` on cancel, or when the paths share
+    /// no directory at all, such as paths on different Windows drives.
+    pub fn common_ancestor(&self) -> Option<PathBuf> {
+        let mut paths = self.paths().into_iter().map(Path::new);
+        let mut common: Vec<Component> = paths.next()?.parent()?.components().collect();
+        for path in paths {
+            let parent = path.parent()?;
+            let shared = common.iter().zip(parent.components()).take_while(|&(a, b)| *a == b).count();
+            common.truncate(shared);
+        }
+
+        if common.is_empty() {
+            None
+        } else {
+            Some(common.iter().collect())
+        }
+    }
+
+    /// The selected paths relative to `common_ancestor`, or unchanged if there is none
+    pub fn paths_relative_to_ancestor(&self) -> Vec<PathBuf> {
+        let ancestor = self.common_ancestor();
+        self.paths().into_iter().map(|path| {
+            match ancestor.as_ref().and_then(|ancestor| Path::new(path).strip_prefix(ancestor).ok()) {
+                Some(relative) => relative.to_path_buf(),
+                None => PathBuf::from(path),
+            }
+        }).collect()
+    }
+
+    /// Hand the path buffers back for reuse by later dialogs on this thread
+    #[cfg(feature = "pool")]
+    pub fn recycle(self) {
+        match self {
+            Response::Okay(path) | Response::OkayWithFilter { path, .. } | Response::OkayReadOnly { path, .. } => {
+                pool::give(path)
+            }
+            Response::OkayTimed { path, .. } => pool::give(path),
+            Response::OkayMultiple(paths) | Response::OkayMultipleTimed { paths, .. } => {
+                paths.into_iter().for_each(pool::give)
+            }
+            Response::OkayDetailed { raw, .. } => pool::give(raw),
+            Response::Cancel | Response::Cancelled { .. } => (),
+        }
+    }
+
+    /// Every selected path, whichever variant this is
+    pub(crate) fn paths(&self) -> Vec<&str> {
+        match *self {
+            Response::Okay(ref path) => vec![path],
+            Response::OkayMultiple(ref paths) | Response::OkayMultipleTimed { ref paths, .. } => {
+                paths.iter().map(String::as_str).collect()
+            }
+            Response::OkayDetailed { ref raw, .. } => vec![raw],
+            Response::OkayWithFilter { ref path, .. } => vec![path],
+            Response::OkayTimed { ref path, .. } => vec![path],
+            Response::OkayReadOnly { ref path, .. } => vec![path],
+            Response::Cancel | Response::Cancelled { .. } => Vec::new(),
+        }
+    }
+}
+
+fn path_mime_type(path: &str) -> Option<String> {
+    let extension = Path::new(path).extension()?.to_str()?;
+    mime::mime_type(extension).map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multiple(paths: &[&str]) -> Response {
+        Response::OkayMultiple(paths.iter().map(|path| path.to_string()).collect())
+    }
+
+    /// A fresh directory under the system temp dir, unique to this test and process
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nfd-response-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks() {
+        let dir = temp_dir("symlinks");
+        let (file, link) = (dir.join("target.txt"), dir.join("link.txt"));
+        fs::write(&file, "").unwrap();
+        ::std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert!(Response::Okay(path_str(&link).to_owned()).is_symlink().unwrap());
+        assert!(!Response::Okay(path_str(&file).to_owned()).is_symlink().unwrap());
+        assert_eq!(multiple(&[path_str(&file), path_str(&link)]).symlink_flags().unwrap(), vec![false, true]);
+        assert!(!Response::Cancel.is_symlink().unwrap());
+
+        // The link itself is inspected, so a dangling one still counts
+        fs::remove_file(&file).unwrap();
+        assert!(Response::Okay(path_str(&link).to_owned()).is_symlink().unwrap());
+        assert!(Response::Okay(path_str(&file).to_owned()).is_symlink().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outcome() {
+        let selected = |paths: &[&str]| Outcome::Selected(paths.iter().map(PathBuf::from).collect());
+        assert_eq!(Response::Okay("/a".to_owned()).outcome(), selected(&["/a"]));
+        assert_eq!(multiple(&["/a", "/b"]).outcome(), selected(&["/a", "/b"]));
+        let detailed = Response::OkayDetailed { raw: "/a".to_owned(), canonical: Some(PathBuf::from("/b")) };
+        assert_eq!(detailed.outcome(), selected(&["/a"]));
+        let with_filter = Response::OkayWithFilter { path: "/a".to_owned(), filter_index: Some(1), filter_changed: true };
+        assert_eq!(with_filter.outcome(), selected(&["/a"]));
+        let read_only = Response::OkayReadOnly { path: "/a".to_owned(), read_only: true };
+        assert_eq!(read_only.outcome(), selected(&["/a"]));
+        assert_eq!(Response::Cancel.outcome(), Outcome::Cancelled);
+        assert_eq!(Response::Cancelled { last_dir: None }.outcome(), Outcome::Cancelled);
+    }
+
+    #[test]
+    fn timed_outcome() {
+        let timed = Response::OkayTimed { path: "/a".to_owned(), elapsed: Duration::from_secs(1) };
+        assert_eq!(timed.outcome(), Outcome::Selected(vec![PathBuf::from("/a")]));
+        let paths = vec!["/a".to_owned(), "/b".to_owned()];
+        let timed = Response::OkayMultipleTimed { paths, elapsed: Duration::from_secs(1) };
+        assert_eq!(timed.outcome(), Outcome::Selected(vec![PathBuf::from("/a"), PathBuf::from("/b")]));
+    }
+
+    #[test]
+    fn mime_types() {
+        let response = multiple(&["/in/a.PNG", "/in/b.pdf", "/in/notes", "/in/c.xyz"]);
+        assert_eq!(response.mime_type().as_deref(), Some("image/png"));
+        assert_eq!(response.mime_types(), vec![
+            Some("image/png".to_owned()),
+            Some("application/pdf".to_owned()),
+            None,
+            None,
+        ]);
+        assert_eq!(Response::Cancel.mime_type(), None);
+    }
+
+    #[test]
+    fn typed_paths() {
+        let response = multiple(&["/in/a.jpg", "/in/b.CSV", "/in/notes", "/in/c.xyz"]);
+        assert_eq!(response.typed_paths(), vec![
+            (PathBuf::from("/in/a.jpg"), Some("image/jpeg".to_owned())),
+            (PathBuf::from("/in/b.CSV"), Some("text/csv".to_owned())),
+            (PathBuf::from("/in/notes"), None),
+            (PathBuf::from("/in/c.xyz"), None),
+        ]);
+        assert!(Response::Cancel.typed_paths().is_empty());
+    }
+
+    #[test]
+    fn sidecars() {
+        let dir = temp_dir("sidecars");
+        let (tagged, untagged) = (dir.join("tagged.jpg"), dir.join("untagged.jpg"));
+        let sidecar = dir.join("tagged.xmp");
+        for path in &[&tagged, &untagged, &sidecar] {
+            fs::write(path, "").unwrap();
+        }
+
+        let response = multiple(&[path_str(&tagged), path_str(&untagged)]);
+        assert_eq!(response.with_sidecar("xmp"), vec![(tagged.clone(), Some(sidecar.clone())), (untagged.clone(), None)]);
+        assert_eq!(response.with_sidecar(".xmp"), response.with_sidecar("xmp"));
+
+        // A file is not its own sidecar
+        let response = Response::Okay(path_str(&sidecar).to_owned());
+        assert_eq!(response.with_sidecar("xmp"), vec![(sidecar.clone(), None)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diff_from() {
+        let previous = [Path::new("/in/a"), Path::new("/in/b"), Path::new("/in/c")];
+        let response = multiple(&["/in/d", "/in/c", "/in/a", "/in/e"]);
+        assert_eq!(response.diff_from(&previous), (
+            vec![PathBuf::from("/in/d"), PathBuf::from("/in/e")],
+            vec![PathBuf::from("/in/b")],
+        ));
+
+        assert_eq!(multiple(&["/in/a", "/in/b", "/in/c"]).diff_from(&previous), (vec![], vec![]));
+        assert_eq!(Response::Okay("/in/x".to_owned()).diff_from(&[]), (vec![PathBuf::from("/in/x")], vec![]));
+        assert_eq!(multiple(&[]).diff_from(&previous).1, previous.iter().map(|path| path.to_path_buf()).collect::<Vec<_>>());
+        assert_eq!(Response::Cancel.diff_from(&previous), (vec![], vec![]));
+        assert_eq!(Response::Cancelled { last_dir: None }.diff_from(&previous), (vec![], vec![]));
+    }
+
+    #[test]
+    fn grouped_by_extension() {
+        let response = multiple(&["/in/a.PNG", "/in/notes", "/in/b.png", "/in/c.tar.gz", "/in/d.Jpg"]);
+        let groups = response.grouped_by_extension();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), vec!["", "gz", "jpg", "png"]);
+        assert_eq!(groups[""], vec![PathBuf::from("/in/notes")]);
+        assert_eq!(groups["png"], vec![PathBuf::from("/in/a.PNG"), PathBuf::from("/in/b.png")]);
+        assert_eq!(groups["jpg"], vec![PathBuf::from("/in/d.Jpg")]);
+        assert!(Response::Cancel.grouped_by_extension().is_empty());
+    }
+
+    #[test]
+    fn common_ancestor() {
+        let response = multiple(&["/photos/2020/a.jpg", "/photos/2021/b.jpg", "/photos/c.jpg"]);
+        assert_eq!(response.common_ancestor(), Some(PathBuf::from("/photos")));
+        assert_eq!(response.paths_relative_to_ancestor(), vec![
+            PathBuf::from("2020/a.jpg"),
+            PathBuf::from("2021/b.jpg"),
+            PathBuf::from("c.jpg"),
+        ]);
+
+        let single = Response::Okay("/photos/a.jpg".to_owned());
+        assert_eq!(single.common_ancestor(), Some(PathBuf::from("/photos")));
+        assert_eq!(multiple(&["/a/x", "/b/y"]).common_ancestor(), Some(PathBuf::from("/")));
+        assert_eq!(Response::Cancel.common_ancestor(), None);
+        assert!(Response::Cancel.paths_relative_to_ancestor().is_empty());
+    }
+
+    #[test]
+    fn no_common_ancestor_across_drives() {
+        let response = multiple(&["C:/photos/a.jpg", "D:/photos/b.jpg"]);
+        assert_eq!(response.common_ancestor(), None);
+        assert_eq!(response.paths_relative_to_ancestor(), vec![
+            PathBuf::from("C:/photos/a.jpg"),
+            PathBuf::from("D:/photos/b.jpg"),
+        ]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_common_ancestor() {
+        let response = multiple(&[r"C:\photos\a.jpg", r"C:\photos\b.jpg"]);
+        assert_eq!(response.common_ancestor(), Some(PathBuf::from(r"C:\photos")));
+        assert_eq!(multiple(&[r"C:\photos\a.jpg", r"D:\photos\b.jpg"]).common_ancestor(), None);
+    }
+}
@@ -0,0 +1,139 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! A thin compatibility layer for code moving between `nfd` and `rfd`, with the
+//! `rfd-compat` feature.
+//!
+//! `FileDialog` mirrors the method names of `rfd::FileDialog` on top of
+//! `DialogBuilder`, and `Response` converts to and from the `Option<PathBuf>` and
+//! `Vec<PathBuf>` that rfd returns. Like rfd, the shim reports errors as if the
+//! user had cancelled, logging them as warnings.
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use {DialogBuilder, DialogType, Filter, Response};
+
+/// A dialog configured with rfd's method names
+pub struct FileDialog {
+    builder: DialogBuilder<'static>,
+    filter: Filter,
+}
+
+impl Default for FileDialog {
+    fn default() -> FileDialog {
+        FileDialog::new()
+    }
+}
+
+impl FileDialog {
+    pub fn new() -> FileDialog {
+        FileDialog {
+            builder: DialogBuilder::new(DialogType::SingleFile),
+            filter: Filter::new(),
+        }
+    }
+
+    /// Add a filter group, e.g. `add_filter("Images", &["png", "jpg"])`
+    pub fn add_filter(mut self, name: &str, extensions: &[&str]) -> FileDialog {
+        self.filter = self.filter.group(name, extensions);
+        self
+    }
+
+    /// The folder the dialog starts in
+    pub fn set_directory<P: AsRef<Path>>(mut self, path: P) -> FileDialog {
+        let path = path.as_ref().to_string_lossy().into_owned();
+        self.builder.default_path = Some(Cow::Owned(path));
+        self
+    }
+
+    /// The file name to suggest, see `DialogBuilder::default_name`
+    pub fn set_file_name(mut self, file_name: &str) -> FileDialog {
+        self.builder.default_name(file_name);
+        self
+    }
+
+    pub fn pick_file(self) -> Option<PathBuf> {
+        self.show(DialogType::SingleFile).into()
+    }
+
+    pub fn pick_files(self) -> Option<Vec<PathBuf>> {
+        let paths: Vec<PathBuf> = self.show(DialogType::MultipleFiles).into();
+        if paths.is_empty() {
+            None
+        } else {
+            Some(paths)
+        }
+    }
+
+    pub fn pick_folder(self) -> Option<PathBuf> {
+        self.show(DialogType::PickFolder).into()
+    }
+
+    pub fn save_file(self) -> Option<PathBuf> {
+        self.show(DialogType::SaveFile).into()
+    }
+
+    fn show(mut self, dialog_type: DialogType) -> Response {
+        if !self.filter.groups().is_empty() {
+            self.builder.filter(&self.filter.to_string());
+        }
+        match self.builder.open_as(dialog_type) {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("nfd: dialog failed, treating it as cancelled: {}", err);
+                Response::Cancel
+            }
+        }
+    }
+}
+
+impl From<Response> for Option<PathBuf> {
+    fn from(response: Response) -> Option<PathBuf> {
+        response.into_path()
+    }
+}
+
+impl From<Response> for Vec<PathBuf> {
+    fn from(response: Response) -> Vec<PathBuf> {
+        response.paths().into_iter().map(PathBuf::from).collect()
+    }
+}
+
+impl From<Option<PathBuf>> for Response {
+    fn from(path: Option<PathBuf>) -> Response {
+        match path {
+            Some(path) => Response::Okay(path.to_string_lossy().into_owned()),
+            None => Response::Cancel,
+        }
+    }
+}
+
+impl From<Vec<PathBuf>> for Response {
+    fn from(paths: Vec<PathBuf>) -> Response {
+        if paths.is_empty() {
+            Response::Cancel
+        } else {
+            Response::OkayMultiple(paths.iter().map(|path| path.to_string_lossy().into_owned()).collect())
+        }
+    }
+}
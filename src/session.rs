@@ -0,0 +1,85 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use memory::DirectoryMemory;
+use {DialogBuilder, DialogType, Response, Result};
+
+/// The key a session's dialogs remember their directory under
+const SESSION_KEY: &str = "session";
+
+/// A group of dialogs, such as the steps of an import wizard, that remember the
+/// last used directory among themselves but each have their own filter.
+///
+/// Each dialog opens where the previous one of the session left off, without
+/// touching the directories remembered by `remember_as` outside the session.
+/// Like every dialog, only one of a session's dialogs can be open at a time; a
+/// second fails with `NFDError::DialogAlreadyOpen`. Clones share the same memory.
+#[derive(Clone)]
+pub struct DialogSession {
+    memory: Arc<DirectoryMemory>,
+}
+
+impl DialogSession {
+    pub fn new() -> DialogSession {
+        DialogSession { memory: Arc::new(DirectoryMemory::new()) }
+    }
+
+    /// A builder for a dialog of this session, for options beyond a filter
+    pub fn builder(&self, dialog_type: DialogType) -> DialogBuilder<'static> {
+        let mut builder = DialogBuilder::new(dialog_type);
+        builder.memory = Some(self.memory.clone());
+        builder.remember_as(SESSION_KEY);
+        builder
+    }
+
+    pub fn pick_file(&self, filter: Option<&str>) -> Result<Response> {
+        self.open(DialogType::SingleFile, filter)
+    }
+
+    pub fn pick_files(&self, filter: Option<&str>) -> Result<Response> {
+        self.open(DialogType::MultipleFiles, filter)
+    }
+
+    pub fn save_file(&self, filter: Option<&str>) -> Result<Response> {
+        self.open(DialogType::SaveFile, filter)
+    }
+
+    pub fn pick_folder(&self) -> Result<Response> {
+        self.open(DialogType::PickFolder, None)
+    }
+
+    fn open(&self, dialog_type: DialogType, filter: Option<&str>) -> Result<Response> {
+        let mut builder = self.builder(dialog_type);
+        if let Some(filter) = filter {
+            builder.filter(filter);
+        }
+        builder.open()
+    }
+}
+
+impl Default for DialogSession {
+    fn default() -> DialogSession {
+        DialogSession::new()
+    }
+}
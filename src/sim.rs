@@ -0,0 +1,187 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! Scripted stand-in for the native dialogs, for integration tests only.
+//!
+//! With the `integration-sim` feature enabled no native dialog is ever shown.
+//! Instead each dialog plays back the next `Script` queued with `enqueue`,
+//! still going through the same validation, default path resolution and
+//! reentrancy guard as a real dialog. Everything that happens is recorded as
+//! an `Event`, so tests can check what a dialog was opened with and what it
+//! did. Don't enable this feature in release builds.
+
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use error::NFDError;
+use ffi::nfdresult_t;
+use pathset::PathSet;
+use {DialogType, Result, Selection};
+
+static SCRIPTS: Mutex<VecDeque<Script>> = Mutex::new(VecDeque::new());
+static EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// What a simulated dialog does, step by step, once it is shown.
+///
+/// Steps run in order until one of `select`, `select_many`, `cancel` or `fail`
+/// closes the dialog; a script that runs out of steps is cancelled.
+#[derive(Default)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+enum Step {
+    Navigate(PathBuf),
+    Run(Box<dyn FnOnce() + Send>),
+    Select(Vec<String>),
+    Cancel,
+    Fail(String),
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script::default()
+    }
+
+    /// Move into `dir`; relative selections after this are resolved against it
+    pub fn navigate<P: Into<PathBuf>>(mut self, dir: P) -> Script {
+        self.steps.push(Step::Navigate(dir.into()));
+        self
+    }
+
+    /// Call `f` while the dialog is showing, e.g. to try opening a second one
+    pub fn run<F: FnOnce() + Send + 'static>(mut self, f: F) -> Script {
+        self.steps.push(Step::Run(Box::new(f)));
+        self
+    }
+
+    /// Pick a single path and press okay
+    pub fn select<S: Into<String>>(mut self, path: S) -> Script {
+        self.steps.push(Step::Select(vec![path.into()]));
+        self
+    }
+
+    /// Pick several paths and press okay
+    pub fn select_many(mut self, paths: &[&str]) -> Script {
+        self.steps.push(Step::Select(paths.iter().map(|path| path.to_string()).collect()));
+        self
+    }
+
+    /// Press cancel
+    pub fn cancel(mut self) -> Script {
+        self.steps.push(Step::Cancel);
+        self
+    }
+
+    /// Close the dialog with `NFDError::Backend`, as if the native dialog failed
+    pub fn fail(mut self, message: &str) -> Script {
+        self.steps.push(Step::Fail(message.to_owned()));
+        self
+    }
+}
+
+/// Something a simulated dialog did
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Shown { dialog_type: DialogType, filter: Option<String>, default_path: Option<String> },
+    Navigated(PathBuf),
+    Selected(Vec<String>),
+    Cancelled,
+    Failed(String),
+}
+
+/// Queue a script to be played back by the next dialog
+pub fn enqueue(script: Script) {
+    SCRIPTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push_back(script);
+}
+
+/// Take every event recorded so far
+pub fn take_events() -> Vec<Event> {
+    let mut events = EVENTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    events.drain(..).collect()
+}
+
+/// Drop any queued scripts and recorded events
+pub fn reset() {
+    SCRIPTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+    EVENTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+}
+
+fn record(event: Event) {
+    EVENTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(event);
+}
+
+/// Stands in for `run_native`. Scripted paths are always valid UTF-8, so
+/// `_replacement` is never needed.
+pub(crate) fn run(filter_list: Option<&CStr>, default_path: Option<&CStr>, dialog_type: DialogType, _replacement: char) -> Result<Selection> {
+    let to_string = |s: &CStr| s.to_string_lossy().into_owned();
+    let default_path = default_path.map(to_string);
+    record(Event::Shown {
+        dialog_type,
+        filter: filter_list.map(to_string),
+        default_path: default_path.clone(),
+    });
+
+    let script = SCRIPTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pop_front();
+    let script = match script {
+        Some(script) => script,
+        None => {
+            let message = "integration-sim: no script queued for this dialog".to_owned();
+            record(Event::Failed(message.clone()));
+            return Err(NFDError::Error(message));
+        }
+    };
+
+    let mut current_dir = default_path.map(PathBuf::from);
+    for step in script.steps {
+        match step {
+            Step::Navigate(dir) => {
+                record(Event::Navigated(dir.clone()));
+                current_dir = Some(dir);
+            }
+            Step::Run(f) => f(),
+            Step::Select(paths) => {
+                let paths: Vec<String> = paths.into_iter().map(|path| match current_dir {
+                    Some(ref dir) => dir.join(path).to_string_lossy().into_owned(),
+                    None => path,
+                }).collect();
+                record(Event::Selected(paths.clone()));
+
+                return Ok(if dialog_type.is_multiple() {
+                    Selection::Multiple(PathSet::from_paths(paths))
+                } else {
+                    Selection::Single(paths.into_iter().next().unwrap_or_default())
+                });
+            }
+            Step::Cancel => break,
+            Step::Fail(message) => {
+                record(Event::Failed(message.clone()));
+                return Err(NFDError::Backend { code: nfdresult_t::NFD_ERROR as i32, platform_code: None, message });
+            }
+        }
+    }
+
+    record(Event::Cancelled);
+    Ok(Selection::Cancel)
+}
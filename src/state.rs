@@ -0,0 +1,131 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use escape::{escape, unescape};
+
+static STATE_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Keep the directories of `DialogBuilder::state_token` dialogs in `path`, e.g.
+/// `dialogs.tsv` in the app's config directory, so they are remembered across
+/// runs. Until this is called `state_token` remembers nothing.
+///
+/// The file has one `token<TAB>directory` line per token, escaped as in the
+/// files of `RecordingDialog`, and is rewritten whenever a directory changes.
+/// Each save is atomic, written to a temporary file that replaces the old one,
+/// but nothing locks the file across processes: if several instances share it,
+/// the last to save wins and directories the others saved in between are lost.
+pub fn set_state_file(path: &Path) {
+    *STATE_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(path.to_path_buf());
+}
+
+/// The directory saved for `token`, if any
+pub(crate) fn load(token: &str) -> Option<PathBuf> {
+    let file = state_file()?;
+    let contents = fs::read_to_string(file).ok()?;
+    parse(&contents).into_iter().find(|(saved, _)| saved == token).map(|(_, dir)| PathBuf::from(dir))
+}
+
+/// Save `dir` for `token`, logging rather than failing if the file can't be written
+pub(crate) fn store(token: &str, dir: &Path) {
+    let file = match state_file() {
+        Some(file) => file,
+        None => {
+            debug!("nfd: no state file set, not saving the directory for `{}`", token);
+            return;
+        }
+    };
+    let dir = match dir.to_str() {
+        Some(dir) => dir,
+        None => return,
+    };
+    if let Err(err) = write(&file, token, dir) {
+        warn!("nfd: couldn't save dialog state to {}: {}", file.display(), err);
+    }
+}
+
+fn state_file() -> Option<PathBuf> {
+    STATE_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+fn write(file: &Path, token: &str, dir: &str) -> io::Result<()> {
+    let mut entries = match fs::read_to_string(file) {
+        Ok(contents) => parse(&contents),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    entries.retain(|(saved, _)| saved != token);
+    entries.push((token.to_owned(), dir.to_owned()));
+
+    let contents: String = entries.iter()
+        .map(|(token, dir)| format!("{}\t{}\n", escape(token), escape(dir)))
+        .collect();
+    // Write then rename, so a crash can't leave the file half written
+    let temp = file.with_extension("tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, file)
+}
+
+fn parse(contents: &str) -> Vec<(String, String)> {
+    contents.lines().filter_map(|line| {
+        let mut fields = line.split('\t');
+        let token = unescape(fields.next()?)?;
+        let dir = unescape(fields.next()?)?;
+        Some((token, dir))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One test, since the state file is global
+    #[test]
+    fn store_and_load() {
+        let dir = std::env::temp_dir().join(format!("nfd-state-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("dialogs.tsv");
+
+        store("unset", &dir);
+        set_state_file(&file);
+        assert_eq!(load("images"), None);
+        assert_eq!(load("unset"), None);
+
+        store("images", Path::new("/tmp/images"));
+        store("odd\ttoken", Path::new("/tmp/a\r\nb"));
+        store("images", Path::new("/tmp/photos"));
+        assert_eq!(load("images"), Some(PathBuf::from("/tmp/photos")));
+        assert_eq!(load("odd\ttoken"), Some(PathBuf::from("/tmp/a\r\nb")));
+        assert_eq!(load("odd"), None);
+        assert_eq!(fs::read_to_string(&file).unwrap().lines().count(), 2);
+        assert!(!file.with_extension("tmp").exists());
+
+        fs::write(&file, "bad\\x\t/tmp/bad\nimages\t/tmp/images\n").unwrap();
+        assert_eq!(load("images"), Some(PathBuf::from("/tmp/images")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
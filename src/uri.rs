@@ -0,0 +1,117 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+/// Turn an absolute path into a `file://` URI as described in RFC 8089.
+///
+/// Windows paths are recognised by their shape rather than the current platform:
+/// `C:\dir\file` becomes `file:///C:/dir/file` and the UNC path
+/// `\\server\share\file` becomes `file://server/share/file`, with `\\?\`
+/// prefixes removed. Anything else is treated as a unix path, where a backslash
+/// is an ordinary character and gets percent-encoded. Bytes outside the URI path
+/// character set, such as spaces, `%`, `#` and non-ASCII, are percent-encoded
+/// as UTF-8.
+///
+/// `None` for relative paths, such as those made by `relative_to`, which have no
+/// `file://` form: their first component would be read as the host.
+pub fn file_uri(path: &str) -> Option<String> {
+    let path = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else if let Some(verbatim) = path.strip_prefix(r"\\?\") {
+        verbatim.to_owned()
+    } else {
+        path.to_owned()
+    };
+
+    let mut uri = String::from("file://");
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        // UNC: the server becomes the authority
+        encode_into(&mut uri, &unc.replace('\\', "/"));
+    } else if is_drive_path(&path) {
+        uri.push('/');
+        encode_into(&mut uri, &path.replace('\\', "/"));
+    } else if path.starts_with('/') {
+        encode_into(&mut uri, &path);
+    } else {
+        return None;
+    }
+    Some(uri)
+}
+
+fn is_drive_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+fn encode_into(uri: &mut String, path: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    for &byte in path.as_bytes() {
+        let keep = byte.is_ascii_alphanumeric() || b"-._~/:@!$&'()*+,;=".contains(&byte);
+        if keep {
+            uri.push(byte as char);
+        } else {
+            uri.push('%');
+            uri.push(HEX[(byte >> 4) as usize] as char);
+            uri.push(HEX[(byte & 0xf) as usize] as char);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_paths() {
+        assert_eq!(file_uri("/home/me/a.txt").unwrap(), "file:///home/me/a.txt");
+        assert_eq!(file_uri("/home/me/my file.txt").unwrap(), "file:///home/me/my%20file.txt");
+        assert_eq!(file_uri("/tmp/100%#1").unwrap(), "file:///tmp/100%25%231");
+        assert_eq!(file_uri(r"/tmp/a\b").unwrap(), "file:///tmp/a%5Cb");
+    }
+
+    #[test]
+    fn unicode() {
+        assert_eq!(file_uri("/tmp/café.png").unwrap(), "file:///tmp/caf%C3%A9.png");
+        assert_eq!(file_uri("/tmp/日本").unwrap(), "file:///tmp/%E6%97%A5%E6%9C%AC");
+    }
+
+    #[test]
+    fn drive_paths() {
+        assert_eq!(file_uri(r"C:\Users\me\a b.txt").unwrap(), "file:///C:/Users/me/a%20b.txt");
+        assert_eq!(file_uri("d:/data").unwrap(), "file:///d:/data");
+        assert_eq!(file_uri(r"\\?\C:\long\path").unwrap(), "file:///C:/long/path");
+    }
+
+    #[test]
+    fn unc_paths() {
+        assert_eq!(file_uri(r"\\server\share\a.txt").unwrap(), "file://server/share/a.txt");
+        assert_eq!(file_uri(r"\\?\UNC\server\share\é").unwrap(), "file://server/share/%C3%A9");
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(file_uri("sub/a.txt"), None);
+        assert_eq!(file_uri(r"sub\a.txt"), None);
+        assert_eq!(file_uri("C:relative"), None);
+        assert_eq!(file_uri(""), None);
+    }
+}
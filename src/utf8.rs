@@ -0,0 +1,94 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+use std::borrow::Cow;
+use std::str;
+
+/// Decode `bytes` as UTF-8, putting `replacement` in place of each invalid
+/// sequence, as `String::from_utf8_lossy` does with U+FFFD
+pub fn decode_lossy(bytes: &[u8], replacement: char) -> Cow<'_, str> {
+    let mut rest = match str::from_utf8(bytes) {
+        Ok(valid) => return Cow::Borrowed(valid),
+        Err(_) => bytes,
+    };
+
+    let mut decoded = String::with_capacity(bytes.len());
+    loop {
+        match str::from_utf8(rest) {
+            Ok(valid) => {
+                decoded.push_str(valid);
+                return Cow::Owned(decoded);
+            }
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                decoded.push_str(str::from_utf8(valid).unwrap_or_default());
+                decoded.push(replacement);
+                match err.error_len() {
+                    Some(len) => rest = &invalid[len..],
+                    // The bytes end in the middle of a sequence
+                    None => return Cow::Owned(decoded),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `decode_lossy` with U+FFFD, which should agree with `from_utf8_lossy`
+    fn standard(bytes: &[u8]) -> String {
+        let decoded = decode_lossy(bytes, '\u{FFFD}').into_owned();
+        assert_eq!(decoded, String::from_utf8_lossy(bytes));
+        decoded
+    }
+
+    #[test]
+    fn valid_is_borrowed() {
+        assert!(matches!(decode_lossy("/tmp/é.png".as_bytes(), '?'), Cow::Borrowed("/tmp/é.png")));
+        assert!(matches!(decode_lossy(b"", '?'), Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn invalid_byte() {
+        assert_eq!(standard(b"/tmp/\xffa.png"), "/tmp/\u{FFFD}a.png");
+        assert_eq!(standard(b"\x80\x80"), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn truncated_sequence() {
+        assert_eq!(standard(b"/tmp/\xe2\x82"), "/tmp/\u{FFFD}");
+        assert_eq!(standard(b"/tmp/\xe2\x82a"), "/tmp/\u{FFFD}a");
+    }
+
+    #[test]
+    fn overlong_and_surrogate() {
+        assert_eq!(standard(b"\xc0\xaf"), "\u{FFFD}\u{FFFD}");
+        assert_eq!(standard(b"a\xed\xa0\x80b"), "a\u{FFFD}\u{FFFD}\u{FFFD}b");
+    }
+
+    #[test]
+    fn custom_replacement() {
+        assert_eq!(decode_lossy(b"a\xffb\xe2\x82", '_'), "a_b_");
+    }
+}
@@ -0,0 +1,83 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! Property tests for the filter parser, the stable counterpart of the fuzz
+//! target in `fuzz/fuzz_targets/filter.rs`
+
+extern crate proptest;
+extern crate nfd;
+
+use nfd::Filter;
+use proptest::prelude::*;
+
+/// Extensions `Filter::try_group` accepts
+fn extension() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_.-]{1,12}"
+}
+
+/// `(name, extensions)` groups of valid extensions
+fn groups() -> impl Strategy<Value = Vec<(String, Vec<String>)>> {
+    prop::collection::vec(("[A-Za-z ]{0,12}", prop::collection::vec(extension(), 1..5)), 1..5)
+}
+
+proptest! {
+    #[test]
+    fn validate_never_panics(filter_list in "\\PC*") {
+        let _ = nfd::validate_filter(&filter_list);
+    }
+
+    #[test]
+    fn validate_never_panics_on_filter_syntax(filter_list in "[a-z ,;\\t]{0,24}") {
+        let _ = nfd::validate_filter(&filter_list);
+    }
+
+    #[test]
+    fn valid_filters_round_trip(groups in groups()) {
+        let mut filter = Filter::new();
+        for (name, extensions) in &groups {
+            let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+            filter = filter.try_group(name, &extensions).unwrap();
+        }
+
+        let filter_list = filter.to_string();
+        prop_assert!(nfd::validate_filter(&filter_list).is_ok());
+        prop_assert_eq!(filter.clone().build(), Ok(filter.clone()));
+
+        let reparsed: Vec<Vec<String>> = filter_list.split(';')
+            .map(|group| group.split(',').map(str::to_owned).collect())
+            .collect();
+        let expected: Vec<Vec<String>> = groups.into_iter().map(|(_, extensions)| extensions).collect();
+        prop_assert_eq!(reparsed, expected);
+    }
+
+    #[test]
+    fn validated_lists_rebuild(filter_list in "[a-z]{1,4}([,;][a-z]{1,4}){0,6}") {
+        prop_assert!(nfd::validate_filter(&filter_list).is_ok());
+        let mut filter = Filter::new();
+        for group in filter_list.split(';') {
+            let extensions: Vec<&str> = group.split(',').collect();
+            filter = filter.try_group(group, &extensions).unwrap();
+        }
+        prop_assert_eq!(filter.to_string(), filter_list);
+        prop_assert!(filter.build().is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+/*
+   Copyright (c) 2016 Saurav Sachidanand
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in
+   all copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+   THE SOFTWARE.
+*/
+
+//! The builder pipeline end to end, on the scripted dialogs of `nfd::sim`.
+//!
+//! `cargo test --features integration-sim`. The scripts and events are global, so
+//! the tests take turns through `LOCK`.
+
+#![cfg(feature = "integration-sim")]
+
+extern crate nfd;
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use nfd::sim::{self, Event, Script};
+use nfd::{DialogType, KnownFolder, NFDError, Response};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Hold the simulation for one test, starting from a clean slate
+fn simulation() -> MutexGuard<'static, ()> {
+    let lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    sim::reset();
+    lock
+}
+
+#[test]
+fn nested_open_fails() {
+    let _sim = simulation();
+    sim::enqueue(Script::new()
+        .run(|| assert!(matches!(nfd::dialog().open(), Err(NFDError::DialogAlreadyOpen))))
+        .select("/tmp/a.txt"));
+    assert!(matches!(nfd::dialog().open().unwrap(), Response::Okay(ref path) if path == "/tmp/a.txt"));
+}
+
+#[test]
+fn records_events() {
+    let _sim = simulation();
+    sim::enqueue(Script::new().navigate("/tmp/in").select("a.png"));
+    sim::enqueue(Script::new().cancel());
+    assert!(matches!(nfd::dialog().filter("png").default_path("/").open().unwrap(), Response::Okay(ref path) if path == "/tmp/in/a.png"));
+    assert!(matches!(nfd::dialog_save().open().unwrap(), Response::Cancel));
+
+    assert_eq!(sim::take_events(), vec![
+        Event::Shown { dialog_type: DialogType::SingleFile, filter: Some("png".to_owned()), default_path: Some("/".to_owned()) },
+        Event::Navigated(PathBuf::from("/tmp/in")),
+        Event::Selected(vec!["/tmp/in/a.png".to_owned()]),
+        Event::Shown { dialog_type: DialogType::SaveFile, filter: None, default_path: None },
+        Event::Cancelled,
+    ]);
+    assert!(sim::take_events().is_empty());
+}
+
+#[test]
+fn records_multiple_selection() {
+    let _sim = simulation();
+    sim::enqueue(Script::new().select_many(&["/tmp/a", "/tmp/b"]));
+    assert!(matches!(nfd::dialog_multiple().open().unwrap(), Response::OkayMultiple(ref paths) if paths.len() == 2));
+    assert_eq!(sim::take_events()[1], Event::Selected(vec!["/tmp/a".to_owned(), "/tmp/b".to_owned()]));
+}
+
+#[test]
+fn remember_as_reuses_directory() {
+    let _sim = simulation();
+    let dir = std::env::temp_dir().join("nfd-sim-remember");
+    std::fs::create_dir_all(&dir).unwrap();
+    let picked = dir.join("picked.txt");
+    sim::enqueue(Script::new().select(picked.to_str().unwrap()));
+    sim::enqueue(Script::new().cancel());
+    sim::enqueue(Script::new().cancel());
+    nfd::dialog().remember_as("sim-remember").open().unwrap();
+    nfd::dialog().remember_as("sim-remember").open().unwrap();
+    nfd::dialog().remember_as("sim-other").open().unwrap();
+
+    let shown = shown_default_paths(sim::take_events());
+    assert_eq!(shown, vec![None, dir.to_str().map(str::to_owned), None]);
+}
+
+#[test]
+fn sessions_are_separate() {
+    let _sim = simulation();
+    let dir = std::env::temp_dir().join("nfd-sim-session");
+    std::fs::create_dir_all(&dir).unwrap();
+    sim::enqueue(Script::new().select(dir.join("x").to_str().unwrap()));
+    for _ in 0..3 {
+        sim::enqueue(Script::new().cancel());
+    }
+    let first = nfd::session();
+    let second = nfd::session();
+    first.pick_file(None).unwrap();
+    first.pick_file(None).unwrap();
+    second.pick_file(None).unwrap();
+    nfd::dialog().open().unwrap();
+
+    let shown = shown_default_paths(sim::take_events());
+    assert_eq!(shown, vec![None, dir.to_str().map(str::to_owned), None, None]);
+}
+
+#[test]
+fn fallbacks_until_remembered() {
+    let _sim = simulation();
+    let dir = std::env::temp_dir().join("nfd-sim-fallbacks");
+    let remembered = dir.join("remembered");
+    std::fs::create_dir_all(&remembered).unwrap();
+    let missing = dir.join("missing");
+    sim::enqueue(Script::new().select(remembered.join("x").to_str().unwrap()));
+    sim::enqueue(Script::new().cancel());
+    for _ in 0..2 {
+        nfd::dialog()
+            .remember_as("sim-fallbacks")
+            .default_path_fallbacks(&[&missing, &dir])
+            .open()
+            .unwrap();
+    }
+
+    let shown = shown_default_paths(sim::take_events());
+    assert_eq!(shown, vec![dir.to_str().map(str::to_owned), remembered.to_str().map(str::to_owned)]);
+}
+
+#[test]
+fn known_folder_below_default_path() {
+    let _sim = simulation();
+    sim::enqueue(Script::new().cancel());
+    sim::enqueue(Script::new().cancel());
+    let temp = std::env::temp_dir();
+    nfd::dialog().default_known_folder(KnownFolder::Temp).open().unwrap();
+    nfd::dialog().default_known_folder(KnownFolder::Temp).default_path("/").open().unwrap();
+
+    let shown = shown_default_paths(sim::take_events());
+    assert_eq!(shown, vec![temp.to_str().map(str::to_owned), Some("/".to_owned())]);
+}
+
+#[test]
+fn warn_empty_directory() {
+    let _sim = simulation();
+    let dir = std::env::temp_dir().join("nfd-sim-empty");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("notes.txt"), "").unwrap();
+    sim::enqueue(Script::new().cancel());
+    let dir_str = dir.to_str().unwrap();
+    let open = || nfd::dialog().filter("png").default_path(dir_str).warn_empty_directory(true).open();
+
+    assert!(matches!(open(), Err(NFDError::NoMatchingFiles { dir: ref empty }) if *empty == dir));
+    assert!(sim::take_events().is_empty());
+    std::fs::write(dir.join("image.PNG"), "").unwrap();
+    assert!(matches!(open().unwrap(), Response::Cancel));
+    assert_eq!(shown_default_paths(sim::take_events()), vec![Some(dir_str.to_owned())]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The default path of every dialog shown, in order
+fn shown_default_paths(events: Vec<Event>) -> Vec<Option<String>> {
+    events.into_iter().filter_map(|event| match event {
+        Event::Shown { default_path, .. } => Some(default_path),
+        _ => None,
+    }).collect()
+}